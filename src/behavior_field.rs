@@ -0,0 +1,83 @@
+use nannou::{color::srgba, geom::Rect, glam::Vec2};
+use rayon::prelude::*;
+
+use crate::{braitenberg_vehicle::{Vehicle, VehicleType}, camera::Camera, environment::Environment, spatial::LightSource};
+
+// time step of the headless vehicle update
+const SAMPLE_DELTA: f32 = 1.0 / 60.0;
+// the arrows point to where the heading would be after this many seconds of turning
+const TURN_LOOKAHEAD: f32 = 0.25;
+const MAX_VELOCITY: f32 = 600.0;
+
+pub struct BehaviorField {
+    pub enabled: bool,
+    pub vehicle_type: VehicleType,
+    // heading of the sampled vehicles in radians, 0.0 points up
+    pub heading: f32,
+    // distance between arrows on screen
+    pub spacing: f32,
+}
+
+impl BehaviorField {
+    pub fn new() -> Self {
+        BehaviorField {
+            enabled: false,
+            vehicle_type: VehicleType::TwoA,
+            heading: 0.0,
+            spacing: 60.0,
+        }
+    }
+
+    // places a vehicle at every grid point, runs one update and draws the resulting speed and turn,
+    // returns the number of primitives
+    pub fn draw<L: LightSource + ?Sized>(&self, draw: &nannou::draw::Draw, camera: &Camera, window: Rect, lights: &L, environment: &Environment) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let spacing = self.spacing / camera.zoom;
+        let world_min = Vec2::new(window.left(), window.bottom()) / camera.zoom + camera.position;
+        let start = (world_min / spacing).floor() * spacing;
+        let columns = (window.w() / self.spacing).ceil() as usize + 2;
+        let rows = (window.h() / self.spacing).ceil() as usize + 2;
+
+        let samples: Vec<(Vec2, f32, f32)> = (0..rows * columns)
+            .into_par_iter()
+            .map(|i| {
+                let world_pos = start + Vec2::new((i % columns) as f32, (i / columns) as f32) * spacing;
+                let mut vehicle = Vehicle::new(self.vehicle_type, world_pos);
+                vehicle.id = usize::MAX;
+                vehicle.orientation = self.heading;
+                vehicle.update(lights, &[], environment, SAMPLE_DELTA);
+                let turn_rate = (vehicle.orientation - self.heading) / SAMPLE_DELTA;
+                (world_pos, vehicle.velocity, turn_rate)
+            })
+            .collect();
+
+        let mut arrows = 0;
+        for (world_pos, velocity, turn_rate) in samples {
+            let direction = self.heading + turn_rate * TURN_LOOKAHEAD;
+            let length = (velocity / MAX_VELOCITY).min(1.0) * self.spacing * 0.8;
+            if length < 1.0 {
+                continue;
+            }
+            let start = (world_pos - camera.position) * camera.zoom;
+            let end = start + Vec2::new(-direction.sin(), direction.cos()) * length;
+            // left turns are green, right turns red, going straight white
+            let turn = (turn_rate * TURN_LOOKAHEAD).clamp(-1.0, 1.0);
+            let color = if turn > 0.0 {
+                srgba(1.0 - turn, 1.0, 1.0 - turn, 0.6)
+            } else {
+                srgba(1.0, 1.0 + turn, 1.0 + turn, 0.6)
+            };
+            draw.arrow()
+                .start(start)
+                .end(end)
+                .weight(2.0)
+                .head_length(8.0)
+                .head_width(4.0)
+                .color(color);
+            arrows += 1;
+        }
+        arrows
+    }
+}
@@ -1,15 +1,33 @@
+use std::borrow::Cow;
+
 use nannou::{color::{srgb, Srgb}, draw::{self}, glam::Vec2, math::Vec2Rotate};
 use serde::{Deserialize, Serialize};
 
-use crate::{camera::Camera, light::Light};
+use crate::{camera::Camera, environment::Environment, spatial::LightSource};
+
+const DEFAULT_WIDTH: f32 = 60.0;
+const DEFAULT_LENGTH: f32 = 100.0;
+const DEFAULT_WHEELBASE: f32 = 60.0;
+const DEFAULT_SENSOR_SIZE: f32 = 10.0;
+const WHEEL_SIZE: (f32, f32) = (10.0, 20.0);
+const DEFAULT_ENERGY: f32 = 100.0;
+// intensity other vehicles have for proximity sensors, equal to a default light
+const VEHICLE_SIGNAL_INTENSITY: f32 = 0.7;
+const SENSOR_GAIN: f32 = 20000.0;
+// the wire joints are only a few pixels wide
+const CIRCLE_RESOLUTION: f32 = 12.0;
+
+// converts the summed intensity / distance^2 at a sensor into the sensor value
+pub fn sensor_response(field: f32) -> f32 {
+    (field * SENSOR_GAIN).min(1.0)
+}
 
-const VEHICLE_WIDTH: f32 = 60.0;
-const VEHICLE_LENGTH: f32 = 100.0;
-const SENSOR_SIZE: f32 = 10.0;
-const LEFT_FRONT: (f32, f32) = (-VEHICLE_WIDTH / 2.0, VEHICLE_LENGTH / 2.0);
-const RIGHT_FRONT: (f32, f32) = (VEHICLE_WIDTH / 2.0, VEHICLE_LENGTH / 2.0);
+// field at which a sensor reads the given value, the inverse of sensor_response below saturation
+pub fn sensor_field(response: f32) -> f32 {
+    response / SENSOR_GAIN
+}
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 pub enum VehicleType {
     TwoA,
     TwoB,
@@ -17,28 +35,173 @@ pub enum VehicleType {
     ThreeB,
 }
 
-#[derive(Serialize, Deserialize)]
+impl VehicleType {
+    pub const ALL: [VehicleType; 4] = [VehicleType::TwoA, VehicleType::TwoB, VehicleType::ThreeA, VehicleType::ThreeB];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            VehicleType::TwoA => "2a",
+            VehicleType::TwoB => "2b",
+            VehicleType::ThreeA => "3a",
+            VehicleType::ThreeB => "3b",
+        }
+    }
+
+    // body color of the vehicle type
+    pub fn color(&self) -> Srgb {
+        match self {
+            VehicleType::TwoA => srgb(1.0, 1.0, 0.0),
+            VehicleType::TwoB => srgb(0.0, 1.0, 1.0),
+            VehicleType::ThreeA => srgb(0.0, 0.0, 1.0),
+            VehicleType::ThreeB => srgb(1.0, 0.0, 1.0),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Side {
+    pub fn opposite(&self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    // -1.0 for the left side, 1.0 for the right side
+    fn sign(&self) -> f32 {
+        match self {
+            Side::Left => -1.0,
+            Side::Right => 1.0,
+        }
+    }
+}
+
+// a sensor mounted on the vehicle, position is in local coordinates (y points forward)
+// side is the side of the vehicle the sensor belongs to, crossed vehicles wire it to the opposite motor
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Sensor {
+    pub position: Vec2,
+    pub side: Side,
+    #[serde(default)]
+    pub kind: SensorKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+pub enum SensorKind {
+    // responds to the lights in the scene
+    #[default]
+    Light,
+    // responds to the distance of other vehicles, optionally only of one type or team
+    Proximity {
+        #[serde(default)]
+        vehicle_type: Option<VehicleType>,
+        #[serde(default)]
+        team: Option<u32>,
+    },
+}
+
+impl SensorKind {
+    fn detects(&self, neighbor: &Neighbor) -> bool {
+        match self {
+            SensorKind::Light => false,
+            SensorKind::Proximity { vehicle_type, team } => {
+                vehicle_type.is_none_or(|t| t == neighbor.vehicle_type)
+                    && team.is_none_or(|t| t == neighbor.team)
+            }
+        }
+    }
+}
+
+// the part of another vehicle that proximity sensors can see
+#[derive(Clone, Copy)]
+pub struct Neighbor {
+    pub id: usize,
+    pub position: Vec2,
+    pub vehicle_type: VehicleType,
+    pub team: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Vehicle {
     vehicle_type: VehicleType,
+    // assigned when the scene is loaded, used to tell vehicles apart
+    #[serde(default)]
+    pub id: usize,
+    #[serde(default)]
+    pub team: u32,
+    // only used in ecosystem mode
+    #[serde(default = "default_energy")]
+    pub energy: f32,
     pub position: Vec2,
     pub orientation: f32,
     pub velocity: f32,
+    #[serde(default = "default_width")]
+    pub width: f32,
+    #[serde(default = "default_length")]
+    pub length: f32,
+    #[serde(default = "default_wheelbase")]
+    pub wheelbase: f32,
+    #[serde(default = "default_sensor_size")]
+    pub sensor_size: f32,
+    // an empty list places one sensor at each front corner of the body
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sensors: Vec<Sensor>,
+    // actual movement of the body, differs from the wheel velocity on slippery ground
+    #[serde(skip)]
+    pub motion: Vec2,
+    // left and right values of the last update, only kept for inspection
+    #[serde(skip)]
+    pub sensor_values: (f32, f32),
+    #[serde(skip)]
+    pub motor_values: (f32, f32),
 }
 
+fn default_width() -> f32 { DEFAULT_WIDTH }
+fn default_length() -> f32 { DEFAULT_LENGTH }
+fn default_wheelbase() -> f32 { DEFAULT_WHEELBASE }
+fn default_sensor_size() -> f32 { DEFAULT_SENSOR_SIZE }
+fn default_energy() -> f32 { DEFAULT_ENERGY }
+
 impl Vehicle {
     pub fn new(vehicle_type: VehicleType, position: Vec2) -> Self {
         Vehicle {
             vehicle_type,
+            id: 0,
+            team: 0,
+            energy: DEFAULT_ENERGY,
             position,
             orientation: 0.0,
             velocity: 0.0,
+            width: DEFAULT_WIDTH,
+            length: DEFAULT_LENGTH,
+            wheelbase: DEFAULT_WHEELBASE,
+            sensor_size: DEFAULT_SENSOR_SIZE,
+            sensors: Vec::new(),
+            motion: Vec2::ZERO,
+            sensor_values: (0.0, 0.0),
+            motor_values: (0.0, 0.0),
         }
     }
 
     // update function for the vehicle
-    pub fn update(&mut self, lights: &[Light], delta: f32) {
-        let left_sensor_value = self.read_sensor(Vec2::from(LEFT_FRONT), lights);
-        let right_sensor_value = self.read_sensor(Vec2::from(RIGHT_FRONT), lights);
+    pub fn update<L: LightSource + ?Sized>(&mut self, lights: &L, neighbors: &[Neighbor], environment: &Environment, delta: f32) {
+        self.sense(lights, neighbors);
+        self.drive(environment, delta);
+    }
+
+    // reads the sensors, the values are used by the next drive
+    pub fn sense<L: LightSource + ?Sized>(&mut self, lights: &L, neighbors: &[Neighbor]) {
+        self.sensor_values = self.read_sensors(lights, neighbors);
+    }
+
+    // moves the vehicle according to the last sensor values
+    pub fn drive(&mut self, environment: &Environment, delta: f32) {
+        let (left_sensor_value, right_sensor_value) = self.sensor_values;
         match self.vehicle_type {
             VehicleType::TwoA => self.two_sensor_vehicle(
                 delta,
@@ -61,23 +224,36 @@ impl Vehicle {
                 (1.0 - left_sensor_value).max(0.0)
             ),
         }
-    }
 
-    // draw function for the vehicle
-    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera, lights: &[Light]) {
-        let color = match self.vehicle_type {
-            VehicleType::TwoA => srgb(1.0, 1.0, 0.0),
-            VehicleType::TwoB => srgb(0.0, 1.0, 1.0),
-            VehicleType::ThreeA => srgb(0.0, 0.0, 1.0),
-            VehicleType::ThreeB => srgb(1.0, 0.0, 1.0),
-        };
-        self.draw_rect(color, Vec2::new(0.0, 0.0), draw, camera, Vec2::new(VEHICLE_WIDTH, VEHICLE_LENGTH));
+        let wheel_velocity = Vec2::new(-self.orientation.sin(), self.orientation.cos()) * self.velocity;
+        self.motion = environment.integrate(&mut self.position, self.motion, wheel_velocity, delta);
+    }
 
+    // draws wheels, sensors and wires on top of the body, the bodies are batched by the renderer,
+    // returns the number of primitives
+    pub fn draw_details(&self, draw: &nannou::draw::Draw, camera: &Camera) -> usize {
         match self.vehicle_type {
-            VehicleType::TwoA => VehicleA::draw(self, draw, camera, lights, srgb(0.0, 1.0, 0.0)),
-            VehicleType::TwoB => VehicleB::draw(self, draw, camera, lights, srgb(0.0, 1.0, 0.0)),
-            VehicleType::ThreeA => VehicleA::draw(self, draw, camera, lights, srgb(1.0, 0.0, 0.0)),
-            VehicleType::ThreeB => VehicleB::draw(self, draw, camera, lights, srgb(1.0, 0.0, 0.0)),
+            VehicleType::TwoA => VehicleA::draw(self, draw, camera, srgb(0.0, 1.0, 0.0)),
+            VehicleType::TwoB => VehicleB::draw(self, draw, camera, srgb(0.0, 1.0, 0.0)),
+            VehicleType::ThreeA => VehicleA::draw(self, draw, camera, srgb(1.0, 0.0, 0.0)),
+            VehicleType::ThreeB => VehicleB::draw(self, draw, camera, srgb(1.0, 0.0, 0.0)),
+        }
+    }
+
+    pub fn vehicle_type(&self) -> VehicleType {
+        self.vehicle_type
+    }
+
+    pub fn set_vehicle_type(&mut self, vehicle_type: VehicleType) {
+        self.vehicle_type = vehicle_type;
+    }
+
+    pub fn neighbor(&self) -> Neighbor {
+        Neighbor {
+            id: self.id,
+            position: self.position,
+            vehicle_type: self.vehicle_type,
+            team: self.team,
         }
     }
 
@@ -88,50 +264,89 @@ impl Vehicle {
     }
 
     // === drawing functions to draw on the vehicle ===
-    pub fn draw_rect(&self, color: Srgb, offset: Vec2, draw: &nannou::draw::Draw, camera: &Camera, dimensions: Vec2) -> Vec2 {
+    fn draw_rect(&self, color: Srgb, offset: Vec2, canvas: &mut Canvas, dimensions: Vec2) -> Vec2 {
+        let camera = canvas.camera;
         let pos = self.to_global_cords(offset, camera);
 
-        draw.rect()
+        canvas.draw.rect()
             .x_y(pos.x, pos.y)
             .w_h( dimensions.x * camera.zoom, dimensions.y * camera.zoom)
             .color(color)
             .rotate(self.orientation);
+        canvas.primitives += 1;
 
         pos
     }
 
-    pub fn draw_line(&self, start: Vec2, end: Vec2, stroke: f32, draw: &nannou::draw::Draw, camera: &Camera, color: Srgb) {
-        draw.line()
+    fn draw_line(&self, start: Vec2, end: Vec2, stroke: f32, canvas: &mut Canvas, color: Srgb) {
+        canvas.draw.line()
             .start(start)
             .end(end)
-            .stroke_weight(stroke * camera.zoom)
+            .stroke_weight(stroke * canvas.camera.zoom)
             .color(color);
+        canvas.primitives += 1;
     }
 
-    pub fn draw_circle(&self, pos: Vec2, radius: f32, draw: &nannou::draw::Draw, camera: &Camera, color: Srgb) {
-        draw.ellipse()
+    fn draw_circle(&self, pos: Vec2, radius: f32, canvas: &mut Canvas, color: Srgb) {
+        canvas.draw.ellipse()
             .x_y(pos.x, pos.y)
-            .radius(radius * camera.zoom)
+            .radius(radius * canvas.camera.zoom)
             .color(color)
-            .resolution(100.0)
+            .resolution(CIRCLE_RESOLUTION)
             .rotate(self.orientation);
+        canvas.primitives += 1;
+    }
+
+    // returns the sensors of the vehicle, falling back to the two front corners
+    pub fn sensors(&self) -> Cow<'_, [Sensor]> {
+        if !self.sensors.is_empty() {
+            return Cow::Borrowed(&self.sensors);
+        }
+        Cow::Owned(vec![
+            Sensor { position: Vec2::new(-self.width / 2.0, self.length / 2.0), side: Side::Left, kind: SensorKind::Light },
+            Sensor { position: Vec2::new(self.width / 2.0, self.length / 2.0), side: Side::Right, kind: SensorKind::Light },
+        ])
+    }
+
+    // returns the averaged sensor values of the left and right side
+    pub fn read_sensors<L: LightSource + ?Sized>(&self, lights: &L, neighbors: &[Neighbor]) -> (f32, f32) {
+        let mut sums = [0.0, 0.0];
+        let mut counts = [0, 0];
+        for sensor in self.sensors().iter() {
+            let i = (sensor.side == Side::Right) as usize;
+            sums[i] += match sensor.kind {
+                SensorKind::Light => self.read_sensor(sensor.position, lights),
+                _ => self.read_proximity_sensor(sensor.position, &sensor.kind, neighbors),
+            };
+            counts[i] += 1;
+        }
+        let average = |i: usize| if counts[i] == 0 { 0.0 } else { sums[i] / counts[i] as f32 };
+        (average(0), average(1))
     }
 
     // returns the sensor value at a given position
-    pub fn read_sensor(&self, pos: Vec2, lights: &[Light]) -> f32 {
+    pub fn read_sensor<L: LightSource + ?Sized>(&self, pos: Vec2, lights: &L) -> f32 {
+        let pos = pos.rotate(self.orientation) + self.position;
+        sensor_response(lights.field_at(pos))
+    }
+
+    // returns the value of a proximity sensor, other vehicles act like lights of a fixed intensity
+    pub fn read_proximity_sensor(&self, pos: Vec2, kind: &SensorKind, neighbors: &[Neighbor]) -> f32 {
         let pos = pos.rotate(self.orientation) + self.position;
-        
-        let val: f32 = lights.iter()
-            .map(|light| {
-                let dist = light.position.distance_squared(pos);
-                light.intensity/dist
+
+        let val: f32 = neighbors.iter()
+            .filter(|neighbor| neighbor.id != self.id && kind.detects(neighbor))
+            .map(|neighbor| {
+                let dist = neighbor.position.distance_squared(pos);
+                VEHICLE_SIGNAL_INTENSITY / dist
             })
-            .sum(); 
-        (val * 20000.0).min(1.0)
+            .sum();
+        sensor_response(val)
     }
 
     // general update function for vehicles with two sensors
     fn two_sensor_vehicle(&mut self, delta: f32, left_sensor_value: f32, right_sensor_value: f32) {        
+        self.motor_values = (left_sensor_value, right_sensor_value);
         let new_vel = (left_sensor_value + right_sensor_value) * 1600.0;
         self.velocity = new_vel.min(600.0);
 
@@ -139,8 +354,6 @@ impl Vehicle {
 
         let rotation = self.calc_rotation(left_sensor_value, right_sensor_value, factor);
         self.orientation += rotation * delta;
-        
-        self.position += Vec2::new(-self.orientation.sin(), self.orientation.cos()) * self.velocity * delta;
     }
 
     // calculates the rotation of the vehicle based on the sensor values
@@ -153,7 +366,7 @@ impl Vehicle {
             return 0.0;
         }
 
-        let h = VEHICLE_WIDTH + VEHICLE_WIDTH / (left_sensor_value / right_sensor_value - 1.0);
+        let h = self.wheelbase + self.wheelbase / (left_sensor_value / right_sensor_value - 1.0);
         let r = (left_sensor_value).atan2(h) * -10000.0;
         r * factor
     }
@@ -162,80 +375,135 @@ impl Vehicle {
 }
 
 // === Draw functions for different vehicle types ===
-struct VehicleA; 
-impl VehicleA {
-    pub fn draw(vehicle: &Vehicle, draw: &draw::Draw, camera: &Camera, lights: &[Light], color: Srgb) {
-        let sensor_size = 10.0;
-        
-        let front_left = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((-VEHICLE_WIDTH + sensor_size) / 2.0, (VEHICLE_LENGTH + sensor_size) / 2.0), draw, camera, Vec2::new(SENSOR_SIZE, SENSOR_SIZE));
-        let front_right = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((VEHICLE_WIDTH - sensor_size) / 2.0, (VEHICLE_LENGTH + sensor_size) / 2.0), draw, camera, Vec2::new(SENSOR_SIZE, SENSOR_SIZE));
-        
-        let back_left = vehicle.to_global_cords(Vec2::new((-VEHICLE_WIDTH + sensor_size) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), camera);
-        let back_right = vehicle.to_global_cords(Vec2::new((VEHICLE_WIDTH - sensor_size) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), camera);
 
-        let left_wheel = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((-VEHICLE_WIDTH - 10.0)  / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), draw, camera, Vec2::new(10.0, 20.0));
-        let right_wheel = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((VEHICLE_WIDTH + 10.0) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), draw, camera, Vec2::new(10.0, 20.0));
+// local positions of the parts every vehicle type draws, derived from the vehicle geometry
+struct Layout {
+    sensor_size: f32,
+    back_y: f32,
+    back_x: f32,
+    wheel_x: f32,
+}
 
-        let left_sensor_val = (vehicle.read_sensor(Vec2::from(LEFT_FRONT), lights) * 25.0).log10();
-        let right_sensor_val = (vehicle.read_sensor(Vec2::from(RIGHT_FRONT), lights) * 25.0).log10();
-        let left_color = srgb(left_sensor_val * color.red, left_sensor_val * color.green, left_sensor_val * color.blue);
-        let right_color = srgb(right_sensor_val * color.red, right_sensor_val * color.green, right_sensor_val * color.blue);
+impl Layout {
+    fn new(vehicle: &Vehicle) -> Self {
+        let sensor_size = vehicle.sensor_size;
+        Layout {
+            sensor_size,
+            back_y: -(vehicle.length - sensor_size) / 2.0,
+            back_x: (vehicle.width - sensor_size) / 2.0,
+            wheel_x: (vehicle.wheelbase + WHEEL_SIZE.0) / 2.0,
+        }
+    }
 
-        vehicle.draw_line(front_left, back_left, 5.0, draw, camera, left_color);
-        vehicle.draw_line(front_right, back_right, 5.0,  draw, camera, right_color);
+    // point where the wires of one side end in front of the motor
+    fn back(&self, side: Side) -> Vec2 {
+        Vec2::new(side.sign() * self.back_x, self.back_y)
+    }
 
-        vehicle.draw_circle(back_left, 2.5, draw, camera, left_color);
-        vehicle.draw_circle(back_right, 2.5, draw, camera, right_color);
+    fn wheel(&self, side: Side) -> Vec2 {
+        Vec2::new(side.sign() * self.wheel_x, self.back_y)
+    }
 
-        vehicle.draw_line(left_wheel, back_left, 5.0, draw, camera, left_color);
-        vehicle.draw_line(right_wheel, back_right, 5.0, draw, camera, right_color);
+    // the sensor square is drawn in front of the mounting point, shifted towards the center line
+    fn sensor(&self, sensor: &Sensor) -> Vec2 {
+        let inward = if sensor.position.x < 0.0 { 1.0 } else if sensor.position.x > 0.0 { -1.0 } else { 0.0 };
+        sensor.position + Vec2::new(inward, 1.0) * self.sensor_size / 2.0
     }
 }
 
-struct VehicleB;
-impl VehicleB {
-    pub fn draw(vehicle: &Vehicle, draw: &draw::Draw, camera: &Camera, lights: &[Light], color: Srgb) {
-        let sensor_size = 10.0;
-        
-        let front_left = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((-VEHICLE_WIDTH + sensor_size) / 2.0, (VEHICLE_LENGTH + sensor_size) / 2.0), draw, camera, Vec2::new(SENSOR_SIZE, SENSOR_SIZE));
-        let front_right = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((VEHICLE_WIDTH - sensor_size) / 2.0, (VEHICLE_LENGTH + sensor_size) / 2.0), draw, camera, Vec2::new(SENSOR_SIZE, SENSOR_SIZE));
-        
-        let back_left = vehicle.to_global_cords(Vec2::new((-VEHICLE_WIDTH + sensor_size) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), camera);
-        let back_right = vehicle.to_global_cords(Vec2::new((VEHICLE_WIDTH - sensor_size) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), camera);
-
-        let front_left_center = (front_left * 2.0 + back_left) / 3.0;
-        let front_right_center = (front_right * 2.0 + back_right) / 3.0;
-
-        let back_left_center = (front_left + back_left * 2.0) / 3.0;
-        let back_right_center = (front_right + back_right * 2.0) / 3.0;
-
-        let left_wheel = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((-VEHICLE_WIDTH - 10.0)  / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), draw, camera, Vec2::new(10.0, 20.0));
-        let right_wheel = vehicle.draw_rect(srgb(1.0, 0.0, 0.0), Vec2::new((VEHICLE_WIDTH + 10.0) / 2.0, -(VEHICLE_LENGTH - sensor_size) / 2.0), draw, camera, Vec2::new(10.0, 20.0));
-
-        let left_sensor_val = (vehicle.read_sensor(Vec2::from(LEFT_FRONT), lights) * 25.0).log10();
-        let right_sensor_val = (vehicle.read_sensor(Vec2::from(RIGHT_FRONT), lights) * 25.0).log10();
-        let left_color = srgb(left_sensor_val * color.red, left_sensor_val * color.green, left_sensor_val * color.blue);
-        let right_color = srgb(right_sensor_val * color.red, right_sensor_val * color.green, right_sensor_val * color.blue);
-
-        vehicle.draw_line(front_left, front_left_center, 5.0, draw, camera, left_color);
-        vehicle.draw_line(front_right, front_right_center, 5.0, draw, camera, right_color);
-        
-        vehicle.draw_line(front_left_center, back_right_center, 5.0, draw, camera, left_color);
-        vehicle.draw_line(front_right_center, back_left_center, 5.0, draw, camera, right_color);
+// where the parts of a vehicle are drawn, counts the primitives for the profiler
+struct Canvas<'a> {
+    draw: &'a draw::Draw,
+    camera: &'a Camera,
+    primitives: usize,
+}
 
-        vehicle.draw_line(back_right_center, back_right, 5.0, draw, camera, left_color);
-        vehicle.draw_line(back_left_center, back_left, 5.0, draw, camera, right_color);
+fn draw_wheels(vehicle: &Vehicle, layout: &Layout, canvas: &mut Canvas) {
+    for side in [Side::Left, Side::Right] {
+        vehicle.draw_rect(srgb(1.0, 0.0, 0.0), layout.wheel(side), canvas, Vec2::from(WHEEL_SIZE));
+    }
+}
 
-        vehicle.draw_line(right_wheel, back_right, 5.0, draw, camera, left_color);
-        vehicle.draw_line(left_wheel, back_left, 5.0, draw, camera, right_color);
+fn draw_sensor(vehicle: &Vehicle, layout: &Layout, sensor: &Sensor, canvas: &mut Canvas) -> Vec2 {
+    let color = match sensor.kind {
+        SensorKind::Light => srgb(1.0, 0.0, 0.0),
+        SensorKind::Proximity { .. } => srgb(1.0, 0.5, 0.0),
+    };
+    vehicle.draw_rect(color, layout.sensor(sensor), canvas, Vec2::new(layout.sensor_size, layout.sensor_size))
+}
 
-        vehicle.draw_circle(back_right, 2.5, draw, camera, left_color);
-        vehicle.draw_circle(back_left, 2.5, draw, camera, right_color);
+// the wires show the sensor values of the last update
+fn wire_colors(vehicle: &Vehicle, color: Srgb) -> (Srgb, Srgb) {
+    let (left_sensor_value, right_sensor_value) = vehicle.sensor_values;
+    let left_sensor_val = (left_sensor_value * 25.0).log10();
+    let right_sensor_val = (right_sensor_value * 25.0).log10();
+    let left_color = srgb(left_sensor_val * color.red, left_sensor_val * color.green, left_sensor_val * color.blue);
+    let right_color = srgb(right_sensor_val * color.red, right_sensor_val * color.green, right_sensor_val * color.blue);
+    (left_color, right_color)
+}
 
-        vehicle.draw_circle(front_left_center, 2.5, draw, camera, left_color);
-        vehicle.draw_circle(front_right_center, 2.5, draw, camera, right_color);
+struct VehicleA; 
+impl VehicleA {
+    pub fn draw(vehicle: &Vehicle, draw: &draw::Draw, camera: &Camera, color: Srgb) -> usize {
+        let layout = Layout::new(vehicle);
+        let mut canvas = Canvas { draw, camera, primitives: 0 };
+        draw_wheels(vehicle, &layout, &mut canvas);
+        let (left_color, right_color) = wire_colors(vehicle, color);
+
+        for side in [Side::Left, Side::Right] {
+            let wire_color = if side == Side::Left { left_color } else { right_color };
+            let back = vehicle.to_global_cords(layout.back(side), camera);
+            let wheel = vehicle.to_global_cords(layout.wheel(side), camera);
+
+            for sensor in vehicle.sensors().iter().filter(|sensor| sensor.side == side) {
+                let front = draw_sensor(vehicle, &layout, sensor, &mut canvas);
+                vehicle.draw_line(front, back, 5.0, &mut canvas, wire_color);
+            }
+
+            vehicle.draw_circle(back, 2.5, &mut canvas, wire_color);
+            vehicle.draw_line(wheel, back, 5.0, &mut canvas, wire_color);
+        }
+        canvas.primitives
+    }
+}
 
-        vehicle.draw_circle(back_right_center, 2.5, draw, camera, left_color);
-        vehicle.draw_circle(back_left_center, 2.5, draw, camera, right_color);
+struct VehicleB;
+impl VehicleB {
+    pub fn draw(vehicle: &Vehicle, draw: &draw::Draw, camera: &Camera, color: Srgb) -> usize {
+        let layout = Layout::new(vehicle);
+        let mut canvas = Canvas { draw, camera, primitives: 0 };
+        draw_wheels(vehicle, &layout, &mut canvas);
+        let (left_color, right_color) = wire_colors(vehicle, color);
+
+        for side in [Side::Left, Side::Right] {
+            let wire_color = if side == Side::Left { left_color } else { right_color };
+            let motor_side = side.opposite();
+            let back = layout.back(motor_side);
+
+            for sensor in vehicle.sensors().iter().filter(|sensor| sensor.side == side) {
+                let front_local = layout.sensor(sensor);
+                let mirrored = Vec2::new(-front_local.x, front_local.y);
+                let front_center = (front_local * 2.0 + layout.back(side)) / 3.0;
+                let back_center = (mirrored + back * 2.0) / 3.0;
+
+                let front = draw_sensor(vehicle, &layout, sensor, &mut canvas);
+                let front_center = vehicle.to_global_cords(front_center, camera);
+                let back_center = vehicle.to_global_cords(back_center, camera);
+                let back = vehicle.to_global_cords(back, camera);
+
+                vehicle.draw_line(front, front_center, 5.0, &mut canvas, wire_color);
+                vehicle.draw_line(front_center, back_center, 5.0, &mut canvas, wire_color);
+                vehicle.draw_line(back_center, back, 5.0, &mut canvas, wire_color);
+
+                vehicle.draw_circle(front_center, 2.5, &mut canvas, wire_color);
+                vehicle.draw_circle(back_center, 2.5, &mut canvas, wire_color);
+            }
+
+            let back = vehicle.to_global_cords(back, camera);
+            let wheel = vehicle.to_global_cords(layout.wheel(motor_side), camera);
+            vehicle.draw_line(wheel, back, 5.0, &mut canvas, wire_color);
+            vehicle.draw_circle(back, 2.5, &mut canvas, wire_color);
+        }
+        canvas.primitives
     }
-}
\ No newline at end of file
+}
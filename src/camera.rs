@@ -1,9 +1,65 @@
-use nannou::{glam::Vec2, state::Mouse};
+use std::f32::consts::{PI, TAU};
+
+use nannou::{geom::Rect, glam::Vec2, math::Vec2Rotate, state::Mouse};
 use serde::{Deserialize, Serialize};
 
+// how fast the smooth camera modes catch up with their target, higher is faster
+const DAMPING: f32 = 4.0;
+const MIN_ZOOM: f32 = 0.02;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    Free,
+    Follow,
+    SmoothFollow,
+    // the view turns so the followed vehicle always faces up
+    FollowRotation,
+    FitAll,
+    GroupCentroid,
+}
+
+impl CameraMode {
+    pub const ALL: [CameraMode; 6] = [
+        CameraMode::Free,
+        CameraMode::Follow,
+        CameraMode::SmoothFollow,
+        CameraMode::FollowRotation,
+        CameraMode::FitAll,
+        CameraMode::GroupCentroid,
+    ];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            CameraMode::Free => "Free",
+            CameraMode::Follow => "Follow Vehicle",
+            CameraMode::SmoothFollow => "Smooth Follow",
+            CameraMode::FollowRotation => "Follow with Rotation",
+            CameraMode::FitAll => "Fit All Vehicles",
+            CameraMode::GroupCentroid => "Follow Group Centroid",
+        }
+    }
+
+    pub fn follows_vehicle(&self) -> bool {
+        matches!(self, CameraMode::Follow | CameraMode::SmoothFollow | CameraMode::FollowRotation)
+    }
+
+    // the mode after or before this one, used to cycle through the modes with the keyboard
+    pub fn cycle(&self, forward: bool) -> CameraMode {
+        let index = CameraMode::ALL.iter().position(|mode| mode == self).unwrap_or(0);
+        let count = CameraMode::ALL.len();
+        let next = if forward { (index + 1) % count } else { (index + count - 1) % count };
+        CameraMode::ALL[next]
+    }
+}
+
+#[derive(Clone)]
 pub struct Camera {
     pub position: Vec2,
     pub zoom: f32,
+    // rotation of the view in radians, the whole drawing is turned by it
+    pub rotation: f32,
+    // window position of the center of the viewport the camera is shown in
+    pub offset: Vec2,
     last_active_mouse_pos: Vec2,
     is_active: bool,
 }
@@ -13,6 +69,8 @@ impl Camera {
         Camera {
             position: Vec2::new(0.0, 0.0),
             zoom: 1.0,
+            rotation: 0.0,
+            offset: Vec2::ZERO,
             last_active_mouse_pos: Vec2::new(0.0, 0.0),
             is_active: false,
         }
@@ -21,15 +79,49 @@ impl Camera {
     pub fn update_pos(&mut self, mouse: &Mouse) {
         if self.is_active {
             let delta = self.last_active_mouse_pos - mouse.position();
-            self.position += delta / self.zoom;
+            self.position += delta.rotate(self.rotation) / self.zoom;
         } 
         self.is_active = mouse.buttons.right().is_down();
         self.last_active_mouse_pos = mouse.position();
     }
 
+    // converts window coordinates to world coordinates
+    pub fn to_world(&self, screen_pos: Vec2) -> Vec2 {
+        (screen_pos - self.offset).rotate(self.rotation) / self.zoom + self.position
+    }
+
+    // converts world coordinates to drawing coordinates, the rotation is applied to the whole drawing
+    pub fn to_screen(&self, world_pos: Vec2) -> Vec2 {
+        (world_pos - self.position) * self.zoom
+    }
+
+    // area in drawing coordinates that covers the window at any rotation
+    pub fn view_rect(&self, window: Rect) -> Rect {
+        if self.rotation == 0.0 {
+            return window;
+        }
+        let size = window.wh().length();
+        Rect::from_w_h(size, size)
+    }
+
     pub fn update_zoom(&mut self, delta: f32) {
         self.zoom *= 1.0 + delta * 0.2;
-        self.zoom = self.zoom.max(0.02);
+        self.zoom = self.zoom.max(MIN_ZOOM);
+    }
+
+    // moves towards the target with exponential damping, the rotation takes the shorter way around
+    pub fn approach(&mut self, position: Vec2, zoom: Option<f32>, rotation: f32, delta: f32) {
+        let t = 1.0 - (-DAMPING * delta).exp();
+        self.position = self.position.lerp(position, t);
+        if let Some(zoom) = zoom {
+            // zoom is approached logarithmically so zooming in and out feel the same
+            self.zoom = (self.zoom.ln() + (zoom.max(MIN_ZOOM).ln() - self.zoom.ln()) * t).exp();
+        }
+        let difference = (rotation - self.rotation + PI).rem_euclid(TAU) - PI;
+        self.rotation = (self.rotation + difference * t).rem_euclid(TAU);
+        if self.rotation.min(TAU - self.rotation) < 1e-4 {
+            self.rotation = 0.0;
+        }
     }
 }
 
@@ -48,6 +140,8 @@ impl<'de> Deserialize<'de> for Camera {
         Ok(Camera {
             position,
             zoom,
+            rotation: 0.0,
+            offset: Vec2::ZERO,
             last_active_mouse_pos: Vec2::new(0.0, 0.0),
             is_active: false,
         })
@@ -0,0 +1,128 @@
+use std::path::{Path, PathBuf};
+
+use nannou::window::Window;
+
+// longest simulation step of an offline render, longer frames are split into several steps
+const MAX_STEP: f32 = 1.0 / 60.0;
+
+struct Session {
+    frame: usize,
+    time: f32,
+    next_frame: f32,
+    // file the frame that is rendered next is saved to
+    pending: Option<PathBuf>,
+}
+
+// saves rendered frames as a numbered png sequence at a fixed interval of simulated time
+pub struct Capture {
+    pub directory: String,
+    // frames per second of the resulting video
+    pub fps: f32,
+    // seconds of simulated time per second of video
+    pub time_scale: f32,
+    pub width: u32,
+    pub height: u32,
+    // advances the simulation by exactly one video frame per update instead of following the real time
+    pub offline: bool,
+    pub hide_gui: bool,
+    session: Option<Session>,
+    // window size before the capture started
+    restore_size: Option<(u32, u32)>,
+}
+
+impl Capture {
+    pub fn new() -> Self {
+        Capture {
+            directory: String::from("capture"),
+            fps: 30.0,
+            time_scale: 1.0,
+            width: 1920,
+            height: 1080,
+            offline: true,
+            hide_gui: true,
+            session: None,
+            restore_size: None,
+        }
+    }
+
+    pub fn is_capturing(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn hides_gui(&self) -> bool {
+        self.is_capturing() && self.hide_gui
+    }
+
+    // number of frames saved so far
+    pub fn frames(&self) -> usize {
+        self.session.as_ref().map_or(0, |session| session.frame)
+    }
+
+    pub fn start(&mut self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        self.session = Some(Session {
+            frame: 0,
+            time: 0.0,
+            next_frame: 0.0,
+            pending: None,
+        });
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.session = None;
+    }
+
+    fn interval(&self) -> f32 {
+        self.time_scale / self.fps
+    }
+
+    // called at the start of every update, resizes the window to the capture resolution and back
+    pub fn begin_frame(&mut self, window: &Window) {
+        match (&mut self.session, self.restore_size) {
+            (Some(session), restore_size) => {
+                session.pending = None;
+                if restore_size.is_none() {
+                    self.restore_size = Some(window.inner_size_pixels());
+                    window.set_inner_size_pixels(self.width, self.height);
+                }
+            }
+            (None, Some((width, height))) => {
+                window.set_inner_size_pixels(width, height);
+                self.restore_size = None;
+            }
+            (None, None) => {}
+        }
+    }
+
+    // number of steps and their length for the next update when rendering offline
+    pub fn offline_steps(&self) -> Option<(u32, f32)> {
+        if self.session.is_none() || !self.offline {
+            return None;
+        }
+        let frame_time = self.interval();
+        let steps = (frame_time / MAX_STEP).ceil().max(1.0);
+        Some((steps as u32, frame_time / steps))
+    }
+
+    // called after the simulation advanced by the given simulated time, decides if the next rendered frame is saved
+    pub fn advance(&mut self, delta: f32) {
+        let interval = self.interval();
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        session.time += delta;
+        // small tolerance so offline frames that land exactly on the interval are not skipped by rounding
+        if session.time + interval * 1e-3 < session.next_frame {
+            return;
+        }
+        session.pending = Some(Path::new(&self.directory).join(format!("frame_{:05}.png", session.frame)));
+        session.frame += 1;
+        // a slow frame in real time mode is captured once instead of several times in a row
+        session.next_frame = (session.next_frame + interval).max(session.time);
+    }
+
+    pub fn pending_frame(&self) -> Option<&Path> {
+        self.session.as_ref()?.pending.as_deref()
+    }
+}
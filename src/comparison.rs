@@ -0,0 +1,173 @@
+use std::time::Instant;
+
+use nannou::{color::srgb, geom::Rect, Draw};
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    braitenberg_vehicle::{Neighbor, Vehicle, VehicleType},
+    light::Light,
+    profiler::{Pass, Stage},
+    render,
+    replace_lights_on_collision,
+    spatial::Falloff,
+    step_vehicles,
+    viewports::Layout,
+    Model,
+};
+
+pub const MAX_COPIES: usize = 4;
+
+// the parameter that differs between the copies
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Parameter {
+    VehicleType,
+    SensorGain,
+    Falloff,
+}
+
+impl Parameter {
+    pub const ALL: [Parameter; 3] = [Parameter::VehicleType, Parameter::SensorGain, Parameter::Falloff];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Parameter::VehicleType => "Vehicle Type",
+            Parameter::SensorGain => "Sensor Gain",
+            Parameter::Falloff => "Light Falloff",
+        }
+    }
+}
+
+// an independent copy of the world
+struct World {
+    label: String,
+    vehicles: Vec<Vehicle>,
+    lights: Vec<Light>,
+    rng: StdRng,
+    gain: f32,
+    exponent: f32,
+}
+
+impl World {
+    // the field the sensors of this copy read, its lights are drawn with it
+    fn falloff(&self) -> Falloff<'_> {
+        Falloff { lights: &self.lights, gain: self.gain, exponent: self.exponent }
+    }
+}
+
+// runs copies of the current scene that differ in one parameter in lockstep from the same seed
+pub struct Comparison {
+    pub parameter: Parameter,
+    pub copies: usize,
+    pub vehicle_types: [VehicleType; MAX_COPIES],
+    // multiplies the sensor readings
+    pub gains: [f32; MAX_COPIES],
+    // exponent of the distance in intensity / distance^exponent
+    pub falloffs: [f32; MAX_COPIES],
+    worlds: Vec<World>,
+}
+
+impl Comparison {
+    pub fn new() -> Self {
+        Comparison {
+            parameter: Parameter::VehicleType,
+            copies: 2,
+            vehicle_types: [VehicleType::TwoA, VehicleType::TwoB, VehicleType::ThreeA, VehicleType::ThreeB],
+            gains: [1.0, 2.0, 0.5, 4.0],
+            falloffs: [2.0, 1.5, 2.5, 3.0],
+            worlds: Vec::new(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.worlds.is_empty()
+    }
+
+    pub fn stop(&mut self) {
+        self.worlds.clear();
+    }
+
+    fn layout(&self) -> Layout {
+        if self.worlds.len() > 2 { Layout::Grid } else { Layout::SideBySide }
+    }
+
+    fn label(&self, copy: usize) -> String {
+        match self.parameter {
+            Parameter::VehicleType => format!("Vehicle Type {}", self.vehicle_types[copy].to_str()),
+            Parameter::SensorGain => format!("Sensor Gain {:.2}", self.gains[copy]),
+            Parameter::Falloff => format!("Falloff 1/d^{:.2}", self.falloffs[copy]),
+        }
+    }
+}
+
+// copies the current world once for every value of the parameter, every copy starts with the seed of the model
+pub fn start(model: &mut Model) {
+    let comparison = &mut model.comparison;
+    comparison.worlds = (0..comparison.copies.clamp(2, MAX_COPIES))
+        .map(|copy| {
+            let mut world = World {
+                label: comparison.label(copy),
+                vehicles: model.vehicles.clone(),
+                lights: model.lights[1..].to_vec(),
+                rng: StdRng::seed_from_u64(model.seed),
+                gain: 1.0,
+                exponent: 2.0,
+            };
+            match comparison.parameter {
+                Parameter::VehicleType => world.vehicles.iter_mut()
+                    .for_each(|vehicle| vehicle.set_vehicle_type(comparison.vehicle_types[copy])),
+                Parameter::SensorGain => world.gain = comparison.gains[copy],
+                Parameter::Falloff => world.exponent = comparison.falloffs[copy],
+            }
+            // the copied sensor values were read with the field of the model, the wires show them until the first step
+            let neighbors: Vec<Neighbor> = world.vehicles.iter().map(Vehicle::neighbor).collect();
+            let falloff = Falloff { lights: &world.lights, gain: world.gain, exponent: world.exponent };
+            world.vehicles.iter_mut().for_each(|vehicle| vehicle.sense(&falloff, &neighbors));
+            world
+        })
+        .collect();
+}
+
+pub fn step(model: &mut Model, steps: u32, delta: f32) {
+    for world in &mut model.comparison.worlds {
+        for _ in 0..steps {
+            let neighbors: Vec<Neighbor> = world.vehicles.iter().map(Vehicle::neighbor).collect();
+            let lights = Falloff { lights: &world.lights, gain: world.gain, exponent: world.exponent };
+            let (sensing, integration) = step_vehicles(&mut world.vehicles, &lights, &neighbors, &model.environment, delta);
+            model.profiler.record(Stage::Sensing, sensing);
+            model.profiler.record(Stage::Integration, integration);
+
+            let start = Instant::now();
+            replace_lights_on_collision(&mut world.lights, &world.vehicles, model.spatial_acceleration, &mut world.rng);
+            model.profiler.record(Stage::LightRelocation, start.elapsed());
+        }
+    }
+}
+
+// draws the copies tiled over the window, all of them are seen through the camera of the model
+pub fn draw(model: &Model, screen_draw: &Draw, window: Rect) {
+    let comparison = &model.comparison;
+    for (world, tile) in comparison.worlds.iter().zip(comparison.layout().rects(window)) {
+        let mut camera = model.camera.clone();
+        camera.offset = tile.xy();
+        let draw = screen_draw.scissor(tile).xy(tile.xy()).rotate(-camera.rotation);
+        let view_rect = camera.view_rect(Rect::from_wh(tile.wh()));
+
+        let profiler = &model.profiler;
+        profiler.pass(Pass::Background, || model.draw_background(&draw, &camera, view_rect));
+        profiler.pass(Pass::Environment, || model.environment.draw(&draw, &camera, view_rect));
+        profiler.pass(Pass::Lights, || render::draw_lights(&draw, &camera, view_rect, &world.falloff()));
+        profiler.pass(Pass::Vehicles, || render::draw_vehicles(&draw, &camera, view_rect, &world.vehicles));
+
+        screen_draw.rect()
+            .xy(tile.xy())
+            .wh(tile.wh())
+            .no_fill()
+            .stroke(srgb(0.3, 0.3, 0.3))
+            .stroke_weight(2.0);
+        screen_draw.text(&world.label)
+            .x_y(tile.x(), tile.top() - 20.0)
+            .w(tile.w())
+            .font_size(18)
+            .color(srgb(1.0, 1.0, 1.0));
+    }
+}
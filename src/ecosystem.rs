@@ -0,0 +1,231 @@
+use std::{collections::BTreeMap, fs::File, io::Write};
+
+use nannou::glam::Vec2;
+use rand::{rngs::StdRng, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{braitenberg_vehicle::Vehicle, light::Light};
+
+// In ecosystem mode the team of a vehicle is its species.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct EcosystemSettings {
+    // pairs of (predator species, prey species)
+    pub predation: Vec<(u32, u32)>,
+    // energy per second a species without prey gains at full light intensity
+    pub light_energy: f32,
+    // share of the energy of an eaten prey that goes to the predator
+    pub eat_efficiency: f32,
+    pub eat_distance: f32,
+    // energy lost per second and per unit travelled
+    pub energy_drain: f32,
+    pub movement_cost: f32,
+    // a vehicle splits its energy with a mutated child once it reaches this energy
+    pub reproduction_energy: f32,
+    // relative amount the geometry of a child differs from its parent
+    pub mutation: f32,
+    pub max_population: usize,
+}
+
+impl Default for EcosystemSettings {
+    fn default() -> Self {
+        EcosystemSettings {
+            predation: Vec::new(),
+            light_energy: 40.0,
+            eat_efficiency: 0.8,
+            eat_distance: 80.0,
+            energy_drain: 2.0,
+            movement_cost: 0.01,
+            reproduction_energy: 200.0,
+            mutation: 0.1,
+            max_population: 500,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PopulationSample {
+    pub time: f32,
+    pub populations: BTreeMap<u32, usize>,
+}
+
+// position of the ecosystem in time, kept by snapshots so that a rewind continues sampling from there
+#[derive(Clone, Copy)]
+pub struct EcosystemState {
+    time: f32,
+    next_sample: f32,
+    samples: usize,
+}
+
+#[derive(Clone)]
+pub struct Ecosystem {
+    pub enabled: bool,
+    pub settings: EcosystemSettings,
+    pub history: Vec<PopulationSample>,
+    pub sample_interval: f32,
+    // file the population history is exported to
+    pub export_path: String,
+    time: f32,
+    next_sample: f32,
+}
+
+impl Ecosystem {
+    pub fn new(settings: Option<EcosystemSettings>) -> Self {
+        Ecosystem {
+            enabled: settings.is_some(),
+            settings: settings.unwrap_or_default(),
+            history: Vec::new(),
+            sample_interval: 0.5,
+            export_path: "population_history.csv".to_string(),
+            time: 0.0,
+            next_sample: 0.0,
+        }
+    }
+
+    pub fn is_predator(&self, species: u32) -> bool {
+        self.settings.predation.iter().any(|&(predator, _)| predator == species)
+    }
+
+    fn is_prey_of(&self, predator: u32, prey: u32) -> bool {
+        self.settings.predation.iter().any(|&(a, b)| a == predator && b == prey)
+    }
+
+    // advances the ecosystem by one simulation step: feeding, predation, death and reproduction
+    pub fn step(&mut self, vehicles: &mut Vec<Vehicle>, lights: &[Light], next_vehicle_id: &mut usize, rng: &mut StdRng, delta: f32) {
+        self.time += delta;
+
+        for vehicle in vehicles.iter_mut() {
+            vehicle.energy -= (self.settings.energy_drain + vehicle.velocity * self.settings.movement_cost) * delta;
+            if !self.is_predator(vehicle.team) {
+                vehicle.energy += vehicle.read_sensor(Vec2::ZERO, lights) * self.settings.light_energy * delta;
+            }
+        }
+
+        self.predation(vehicles);
+        vehicles.retain(|vehicle| vehicle.energy > 0.0);
+        self.reproduction(vehicles, next_vehicle_id, rng);
+
+        if self.time >= self.next_sample {
+            self.record(vehicles);
+            self.next_sample = self.time + self.sample_interval;
+        }
+    }
+
+    // predators eat the prey they touch, eaten prey is left with no energy
+    fn predation(&self, vehicles: &mut [Vehicle]) {
+        let eat_distance_squared = self.settings.eat_distance * self.settings.eat_distance;
+        for predator in 0..vehicles.len() {
+            if !self.is_predator(vehicles[predator].team) || vehicles[predator].energy <= 0.0 {
+                continue;
+            }
+            for prey in 0..vehicles.len() {
+                if prey == predator
+                    || vehicles[prey].energy <= 0.0
+                    || !self.is_prey_of(vehicles[predator].team, vehicles[prey].team)
+                    || vehicles[predator].position.distance_squared(vehicles[prey].position) > eat_distance_squared
+                {
+                    continue;
+                }
+                let gained = vehicles[prey].energy * self.settings.eat_efficiency;
+                vehicles[predator].energy += gained;
+                vehicles[prey].energy = 0.0;
+            }
+        }
+    }
+
+    fn reproduction(&self, vehicles: &mut Vec<Vehicle>, next_vehicle_id: &mut usize, rng: &mut StdRng) {
+        // parents at the population limit keep their energy and no ids are used up
+        let mut free = self.settings.max_population.saturating_sub(vehicles.len());
+        let mut children = Vec::new();
+        for parent in vehicles.iter_mut() {
+            if free == 0 {
+                break;
+            }
+            if parent.energy < self.settings.reproduction_energy {
+                continue;
+            }
+            free -= 1;
+            parent.energy /= 2.0;
+            let mut child = parent.clone();
+            child.id = *next_vehicle_id;
+            *next_vehicle_id += 1;
+            child.position += Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0));
+            child.orientation = rng.gen_range(0.0..std::f32::consts::TAU);
+            mutate(&mut child, self.settings.mutation, rng);
+            children.push(child);
+        }
+        vehicles.extend(children);
+    }
+
+    fn record(&mut self, vehicles: &[Vehicle]) {
+        let mut populations = BTreeMap::new();
+        for species in self.settings.predation.iter().flat_map(|&(a, b)| [a, b]) {
+            populations.insert(species, 0);
+        }
+        for vehicle in vehicles {
+            *populations.entry(vehicle.team).or_insert(0) += 1;
+        }
+        self.history.push(PopulationSample { time: self.time, populations });
+    }
+
+    pub fn state(&self) -> EcosystemState {
+        EcosystemState { time: self.time, next_sample: self.next_sample, samples: self.history.len() }
+    }
+
+    // the history is only cut back, samples dropped by an earlier restore do not come back
+    pub fn restore(&mut self, state: EcosystemState) {
+        self.time = state.time;
+        self.next_sample = state.next_sample;
+        self.history.truncate(state.samples);
+    }
+
+    pub fn reset(&mut self, settings: Option<EcosystemSettings>) {
+        let export_path = std::mem::take(&mut self.export_path);
+        *self = Ecosystem::new(settings);
+        self.export_path = export_path;
+    }
+
+    pub fn species(&self) -> Vec<u32> {
+        let mut species: Vec<u32> = self.history.iter()
+            .flat_map(|sample| sample.populations.keys().copied())
+            .collect();
+        species.sort();
+        species.dedup();
+        species
+    }
+
+    // writes the population history as csv with one column per species
+    pub fn export_history(&self, path: &str) -> std::io::Result<()> {
+        let species = self.species();
+        let mut file = File::create(path)?;
+        write!(file, "time")?;
+        for s in &species {
+            write!(file, ",species_{s}")?;
+        }
+        writeln!(file)?;
+        for sample in &self.history {
+            write!(file, "{}", sample.time)?;
+            for s in &species {
+                write!(file, ",{}", sample.populations.get(s).copied().unwrap_or(0))?;
+            }
+            writeln!(file)?;
+        }
+        Ok(())
+    }
+}
+
+// randomly changes the body and sensor placement of a vehicle by up to the given fraction
+fn mutate(vehicle: &mut Vehicle, amount: f32, rng: &mut StdRng) {
+    if amount <= 0.0 {
+        return;
+    }
+    let mut jitter = |value: f32| value * (1.0 + rng.gen_range(-amount..amount));
+    vehicle.width = jitter(vehicle.width);
+    vehicle.length = jitter(vehicle.length);
+    vehicle.wheelbase = jitter(vehicle.wheelbase);
+    let mut sensors = vehicle.sensors().into_owned();
+    for sensor in sensors.iter_mut() {
+        sensor.position = Vec2::new(jitter(sensor.position.x), jitter(sensor.position.y));
+    }
+    vehicle.sensors = sensors;
+}
@@ -0,0 +1,190 @@
+use nannou::{color::{srgb, srgba}, glam::Vec2, state::Mouse};
+
+use crate::{braitenberg_vehicle::{Vehicle, VehicleType}, camera::Camera, history::{Command, History}, light::Light, selection::{Selected, Selection}, Model, GRID_SIZE};
+
+// distance of the rotation handle in front of the selected vehicle
+const HANDLE_DISTANCE: f32 = 80.0;
+// radius of the rotation handle on screen
+const HANDLE_RADIUS: f32 = 8.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tool {
+    Select,
+    Vehicle(VehicleType),
+    Light,
+}
+
+impl Tool {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Tool::Select => "Select",
+            Tool::Vehicle(vehicle_type) => vehicle_type.to_str(),
+            Tool::Light => "Light",
+        }
+    }
+}
+
+pub struct Editor {
+    pub enabled: bool,
+    pub tool: Tool,
+    // snaps to the centers and corners of the background tiles
+    pub snap_to_grid: bool,
+    // the edited scene is saved here so the shipped scenes are not overwritten
+    pub scene_path: String,
+    // id of the vehicle being rotated with the handle
+    rotating: Option<usize>,
+    was_down: bool,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Editor {
+            enabled: false,
+            tool: Tool::Select,
+            snap_to_grid: false,
+            scene_path: String::from("scenes/user_scene.json"),
+            rotating: None,
+            was_down: false,
+        }
+    }
+
+    pub fn snap(&self, pos: Vec2) -> Vec2 {
+        if !self.snap_to_grid {
+            return pos;
+        }
+        let step = GRID_SIZE / 2.0;
+        (pos / step).round() * step
+    }
+
+    // keeps dragged objects on the grid
+    pub fn snap_selection(&self, selection: &Selection, vehicles: &mut [Vehicle], lights: &mut [Light]) {
+        if !self.enabled || !self.snap_to_grid || !selection.is_dragging() {
+            return;
+        }
+        match selection.selected {
+            Some(Selected::Vehicle(id)) => {
+                if let Some(vehicle) = vehicles.iter_mut().find(|vehicle| vehicle.id == id) {
+                    vehicle.position = self.snap(vehicle.position);
+                }
+            }
+            Some(Selected::Light(index)) => {
+                if let Some(light) = lights.get_mut(index) {
+                    light.position = self.snap(light.position);
+                }
+            }
+            None => {}
+        }
+    }
+
+    pub fn delete_selected(&self, selection: &mut Selection, vehicles: &mut Vec<Vehicle>, lights: &mut Vec<Light>, history: &mut History) {
+        if !self.enabled {
+            return;
+        }
+        match selection.selected {
+            Some(Selected::Vehicle(id)) => {
+                if let Some(index) = vehicles.iter().position(|vehicle| vehicle.id == id) {
+                    let vehicle = vehicles.remove(index);
+                    history.push(Command::RemoveVehicle { index, vehicle });
+                }
+            }
+            Some(Selected::Light(index)) if index > 0 && index < lights.len() => {
+                let light = lights.remove(index);
+                history.push(Command::RemoveLight { index, light });
+            }
+            _ => {}
+        }
+        selection.clear();
+    }
+
+    // id and world position of the rotation handle of the selected vehicle
+    fn handle(&self, camera: &Camera, vehicles: &[Vehicle], selection: &Selection) -> Option<(usize, Vec2)> {
+        let Some(Selected::Vehicle(id)) = selection.selected else {
+            return None;
+        };
+        let vehicle = vehicles.iter().find(|vehicle| vehicle.id == id)?;
+        let heading = Vec2::new(-vehicle.orientation.sin(), vehicle.orientation.cos());
+        Some((id, vehicle.position + heading * (vehicle.length / 2.0 + HANDLE_DISTANCE / camera.zoom)))
+    }
+
+    // returns the number of primitives
+    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera, vehicles: &[Vehicle], selection: &Selection) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let Some((id, handle)) = self.handle(camera, vehicles, selection) else {
+            return 0;
+        };
+        let Some(vehicle) = vehicles.iter().find(|vehicle| vehicle.id == id) else {
+            return 0;
+        };
+        let color = srgba(1.0, 1.0, 1.0, 0.8);
+        let handle = camera.to_screen(handle);
+        draw.line()
+            .start(camera.to_screen(vehicle.position))
+            .end(handle)
+            .stroke_weight(1.5)
+            .color(color);
+        draw.ellipse()
+            .x_y(handle.x, handle.y)
+            .radius(HANDLE_RADIUS)
+            .color(color);
+        2
+    }
+}
+
+// places objects with the palette tools and rotates the selected vehicle with its handle.
+// returns true if the mouse press was used by the editor and should not change the selection.
+pub fn update(model: &mut Model, mouse: &Mouse, camera: &Camera, pointer_over_gui: bool) -> bool {
+    let Model { editor, vehicles, lights, selection, next_vehicle_id, history, .. } = model;
+    let is_down = mouse.buttons.left().is_down();
+    let pressed = is_down && !editor.was_down;
+    editor.was_down = is_down;
+    if !is_down {
+        editor.rotating = None;
+    }
+    if !editor.enabled {
+        return false;
+    }
+    let mouse_pos = camera.to_world(mouse.position());
+
+    if let Some(id) = editor.rotating {
+        if let Some(vehicle) = vehicles.iter_mut().find(|vehicle| vehicle.id == id) {
+            let direction = mouse_pos - vehicle.position;
+            vehicle.orientation = (-direction.x).atan2(direction.y);
+        }
+        return true;
+    }
+
+    if !pressed || pointer_over_gui {
+        return false;
+    }
+
+    match editor.tool {
+        Tool::Select => {
+            let handle = editor.handle(camera, vehicles, selection);
+            if let Some((id, handle)) = handle {
+                if handle.distance(mouse_pos) * camera.zoom <= HANDLE_RADIUS * 1.5 {
+                    editor.rotating = Some(id);
+                    return true;
+                }
+            }
+            false
+        }
+        Tool::Vehicle(vehicle_type) => {
+            let mut vehicle = Vehicle::new(vehicle_type, editor.snap(mouse_pos));
+            vehicle.id = *next_vehicle_id;
+            *next_vehicle_id += 1;
+            selection.selected = Some(Selected::Vehicle(vehicle.id));
+            history.push(Command::AddVehicle(vehicle.clone()));
+            vehicles.push(vehicle);
+            true
+        }
+        Tool::Light => {
+            let light = Light::new(editor.snap(mouse_pos), srgb(1.0, 1.0, 1.0), 0.7);
+            history.push(Command::AddLight { index: lights.len(), light: light.clone() });
+            lights.push(light);
+            selection.selected = Some(Selected::Light(lights.len() - 1));
+            true
+        }
+    }
+}
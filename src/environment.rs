@@ -0,0 +1,185 @@
+use nannou::{color::{srgba, Srgba}, geom::Rect, glam::Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::camera::Camera;
+
+// how fast the motion of a vehicle follows its wheels at a friction of 1.0
+const FRICTION_RATE: f32 = 20.0;
+const ARROW_SPACING: f32 = 80.0;
+
+// vector field that pushes vehicles, values are in units per second
+#[derive(Serialize, Deserialize, Clone)]
+pub enum FlowField {
+    Constant {
+        velocity: Vec2,
+    },
+    // rotates counter clockwise around the center for a positive strength
+    Vortex {
+        center: Vec2,
+        strength: f32,
+        radius: f32,
+    },
+    // vectors are stored row by row starting at the bottom left corner of the grid
+    Grid {
+        origin: Vec2,
+        cell_size: f32,
+        columns: usize,
+        vectors: Vec<Vec2>,
+    },
+}
+
+impl FlowField {
+    pub fn sample(&self, pos: Vec2) -> Vec2 {
+        match self {
+            FlowField::Constant { velocity } => *velocity,
+            FlowField::Vortex { center, strength, radius } => {
+                let offset = pos - *center;
+                let dist = offset.length();
+                if dist == 0.0 {
+                    return Vec2::ZERO;
+                }
+                // rigid rotation inside the core, falling off with the distance outside of it
+                let speed = if dist < *radius { strength * dist / radius } else { strength * radius / dist };
+                Vec2::new(-offset.y, offset.x) / dist * speed
+            }
+            FlowField::Grid { origin, cell_size, columns, vectors } => {
+                if *columns == 0 || vectors.len() < *columns {
+                    return Vec2::ZERO;
+                }
+                let rows = vectors.len() / columns;
+                let cell = (pos - *origin) / *cell_size;
+                if cell.x < 0.0 || cell.y < 0.0 || cell.x > (columns - 1) as f32 || cell.y > rows.saturating_sub(1) as f32 {
+                    return Vec2::ZERO;
+                }
+                let x0 = (cell.x.floor() as usize).min(columns - 1);
+                let y0 = (cell.y.floor() as usize).min(rows - 1);
+                let x1 = (x0 + 1).min(columns - 1);
+                let y1 = (y0 + 1).min(rows - 1);
+                let tx = cell.x - x0 as f32;
+                let ty = cell.y - y0 as f32;
+                let at = |x: usize, y: usize| vectors[y * columns + x];
+                let bottom = at(x0, y0).lerp(at(x1, y0), tx);
+                let top = at(x0, y1).lerp(at(x1, y1), tx);
+                bottom.lerp(top, ty)
+            }
+        }
+    }
+}
+
+// rectangular region that changes how vehicles move, e.g. mud or ice
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Zone {
+    pub min: Vec2,
+    pub max: Vec2,
+    // scales the speed the wheels produce
+    #[serde(default = "default_multiplier")]
+    pub speed_multiplier: f32,
+    // 1.0 is normal ground, lower values let vehicles slide
+    #[serde(default = "default_multiplier")]
+    pub friction: f32,
+}
+
+fn default_multiplier() -> f32 { 1.0 }
+
+impl Zone {
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.x >= self.min.x && pos.x <= self.max.x && pos.y >= self.min.y && pos.y <= self.max.y
+    }
+
+    fn color(&self) -> Srgba {
+        if self.friction < 1.0 {
+            srgba(0.5, 0.8, 1.0, 0.12)
+        } else if self.speed_multiplier < 1.0 {
+            srgba(0.5, 0.3, 0.1, 0.25)
+        } else {
+            srgba(0.2, 1.0, 0.2, 0.1)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Environment {
+    #[serde(default)]
+    pub flow_fields: Vec<FlowField>,
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+}
+
+impl Environment {
+    pub fn is_empty(&self) -> bool {
+        self.flow_fields.is_empty() && self.zones.is_empty()
+    }
+
+    // sum of all flow fields at the given position
+    pub fn flow_at(&self, pos: Vec2) -> Vec2 {
+        self.flow_fields.iter().fold(Vec2::ZERO, |flow, field| flow + field.sample(pos))
+    }
+
+    // speed multiplier and friction at the given position, overlapping zones multiply
+    pub fn ground_at(&self, pos: Vec2) -> (f32, f32) {
+        self.zones.iter()
+            .filter(|zone| zone.contains(pos))
+            .fold((1.0, 1.0), |(speed, friction), zone| (speed * zone.speed_multiplier, friction * zone.friction))
+    }
+
+    // moves a body whose wheels produce the given velocity, returns the new motion of the body
+    pub fn integrate(&self, position: &mut Vec2, motion: Vec2, wheel_velocity: Vec2, delta: f32) -> Vec2 {
+        let (speed_multiplier, friction) = self.ground_at(*position);
+        let target = wheel_velocity * speed_multiplier;
+        let motion = if friction >= 1.0 {
+            target
+        } else {
+            motion + (target - motion) * (friction * FRICTION_RATE * delta).min(1.0)
+        };
+        *position += (motion + self.flow_at(*position)) * delta;
+        motion
+    }
+
+    // returns the number of primitives
+    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera, window: Rect) -> usize {
+        for zone in &self.zones {
+            let min = (zone.min - camera.position) * camera.zoom;
+            let max = (zone.max - camera.position) * camera.zoom;
+            let center = (min + max) / 2.0;
+            let size = max - min;
+            draw.rect()
+                .x_y(center.x, center.y)
+                .w_h(size.x, size.y)
+                .color(zone.color());
+        }
+
+        if self.flow_fields.is_empty() {
+            return self.zones.len();
+        }
+        // arrows sit on a grid in world coordinates so they move with the camera
+        let spacing = ARROW_SPACING / camera.zoom;
+        let world_min = Vec2::new(window.left(), window.bottom()) / camera.zoom + camera.position;
+        let start = (world_min / spacing).floor() * spacing;
+        let columns = (window.w() / ARROW_SPACING).ceil() as i32 + 1;
+        let rows = (window.h() / ARROW_SPACING).ceil() as i32 + 1;
+        let mut arrows = 0;
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let world_pos = start + Vec2::new(column as f32, row as f32) * spacing;
+                let screen_pos = (world_pos - camera.position) * camera.zoom;
+                let flow = self.flow_at(world_pos);
+                let length = flow.length();
+                if length < 1.0 {
+                    continue;
+                }
+                // arrows grow with the flow speed but never reach into the next arrow
+                let arrow_length = (length * camera.zoom).min(ARROW_SPACING * 0.8);
+                let end = screen_pos + flow / length * arrow_length;
+                draw.arrow()
+                    .start(screen_pos)
+                    .end(end)
+                    .weight(1.5)
+                    .head_length(6.0)
+                    .head_width(3.0)
+                    .color(srgba(1.0, 1.0, 1.0, 0.15));
+                arrows += 1;
+            }
+        }
+        self.zones.len() + arrows
+    }
+}
@@ -1,6 +1,6 @@
 use nannou::{event::{MouseScrollDelta, TouchPhase}, App};
 
-use crate::{scene::Scenes, Model};
+use crate::{history, rewind, scene::Scenes, Model};
 
 pub fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::event::WindowEvent) {
     model.egui.handle_raw_event(event);
@@ -8,7 +8,7 @@ pub fn raw_window_event(_app: &App, model: &mut Model, event: &nannou::winit::ev
 
 pub fn handle_mouse_wheel(_app: &App, model: &mut Model, delta: MouseScrollDelta, _state: TouchPhase) {
     match delta {
-        MouseScrollDelta::LineDelta(_x, y) => model.camera.update_zoom(y),
+        MouseScrollDelta::LineDelta(_x, y) => model.active_camera_mut().update_zoom(y),
         MouseScrollDelta::PixelDelta(_pos) => {}
     }
 }
@@ -22,7 +22,36 @@ pub fn handle_key_released(app: &App, model: &mut Model, key: nannou::event::Key
         nannou::event::Key::Key5 => model.current_scene = Scenes::Scene5,
         nannou::event::Key::Key6 => model.current_scene = Scenes::Scene6,
         nannou::event::Key::Key7 => model.current_scene = Scenes::Scene7,
+        nannou::event::Key::Key8 => model.current_scene = Scenes::Scene8,
+        nannou::event::Key::Key9 => model.current_scene = Scenes::Scene9,
+        nannou::event::Key::Key0 => model.current_scene = Scenes::Scene10,
         nannou::event::Key::Space => model.paused = !model.paused,
+        nannou::event::Key::Delete | nannou::event::Key::Back if !model.egui.ctx().wants_keyboard_input() && model.replays.playback.is_none() => {
+            model.editor.delete_selected(&mut model.selection, &mut model.vehicles, &mut model.lights, &mut model.history);
+        }
+        nannou::event::Key::Z if app.keys.mods.ctrl() && !model.egui.ctx().wants_keyboard_input() && model.replays.playback.is_none() => {
+            if app.keys.mods.shift() {
+                history::redo(model);
+            } else {
+                history::undo(model);
+            }
+        }
+        nannou::event::Key::C if !model.egui.ctx().wants_keyboard_input() => {
+            let camera_mode = match model.viewports.active {
+                0 => &mut model.camera_mode,
+                i => &mut model.viewports.views[i - 1].camera_mode,
+            };
+            *camera_mode = camera_mode.cycle(!app.keys.mods.shift());
+        }
+        nannou::event::Key::R if !model.egui.ctx().wants_keyboard_input() && model.replays.playback.is_none() => rewind::rewind(model),
+        nannou::event::Key::F12 => {
+            if model.capture.is_capturing() {
+                model.capture.stop();
+            } else if let Err(err) = model.capture.start() {
+                eprintln!("Failed to start capture to {}: {err}", model.capture.directory);
+            }
+        }
+        nannou::event::Key::F3 => model.profiler.show_hud = !model.profiler.show_hud,
         nannou::event::Key::F11 => {
             let window = app.main_window();
             window.set_fullscreen(!window.is_fullscreen());
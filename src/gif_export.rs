@@ -0,0 +1,141 @@
+use std::{error::Error, fs::File, path::Path};
+
+use nannou::{image::{self, imageops::FilterType}, App};
+
+use crate::{capture::Capture, rewind, Model};
+
+// frames of the export are captured here and removed once the gif is written
+const FRAME_DIRECTORY: &str = "gif_frames";
+
+// renders the last seconds of the simulation again from the rewind buffer and saves them as an animated gif
+pub struct GifExport {
+    pub path: String,
+    pub seconds: f32,
+    pub fps: f32,
+    pub width: u32,
+    pub height: u32,
+    // 1 gives the best palette, 30 the fastest quantization
+    pub quantization_speed: i32,
+    pub loop_forever: bool,
+    // number of repetitions when the gif does not loop forever
+    pub loop_count: u16,
+    // simulated seconds left to render
+    remaining: Option<f32>,
+    // capture settings of the user, restored after the export
+    capture: Option<Capture>,
+    paused: bool,
+}
+
+impl GifExport {
+    pub fn new() -> Self {
+        GifExport {
+            path: String::from("export.gif"),
+            seconds: 5.0,
+            fps: 15.0,
+            width: 480,
+            height: 320,
+            quantization_speed: 10,
+            loop_forever: true,
+            loop_count: 1,
+            remaining: None,
+            capture: None,
+            paused: false,
+        }
+    }
+
+    pub fn is_exporting(&self) -> bool {
+        self.remaining.is_some()
+    }
+
+    // called after the simulation advanced by the given simulated time
+    pub fn advance(&mut self, delta: f32) {
+        if let Some(remaining) = &mut self.remaining {
+            *remaining -= delta;
+        }
+    }
+}
+
+// goes back in time and starts an offline capture at the gif resolution
+pub fn start(model: &mut Model) {
+    if model.gif_export.is_exporting() || model.capture.is_capturing() {
+        return;
+    }
+    // the rewind buffer belongs to the hidden live world, not to the compared copies
+    if model.comparison.is_active() {
+        eprintln!("Failed to export gif: not available while comparing");
+        return;
+    }
+    let seconds = model.gif_export.seconds.min(model.rewind.available());
+    if seconds <= 0.0 {
+        eprintln!("Failed to export gif: the rewind buffer is empty");
+        return;
+    }
+    let Some(seconds) = rewind::restore(model, seconds) else {
+        return;
+    };
+    let mut capture = Capture::new();
+    capture.directory = String::from(FRAME_DIRECTORY);
+    capture.fps = model.gif_export.fps;
+    capture.width = model.gif_export.width;
+    capture.height = model.gif_export.height;
+    if let Err(err) = capture.start() {
+        eprintln!("Failed to export gif: {err}");
+        return;
+    }
+    model.gif_export.capture = Some(std::mem::replace(&mut model.capture, capture));
+    model.gif_export.remaining = Some(seconds);
+    model.gif_export.paused = model.paused;
+    model.paused = false;
+}
+
+// finishes the export once all frames are rendered, called at the start of every update
+pub fn update(app: &App, model: &mut Model) {
+    if !model.gif_export.remaining.is_some_and(|remaining| remaining <= 0.0) {
+        return;
+    }
+    let window = app.main_window();
+    model.capture.stop();
+    model.capture.begin_frame(&window);
+    if let Some(capture) = model.gif_export.capture.take() {
+        model.capture = capture;
+    }
+    model.gif_export.remaining = None;
+    model.paused = model.gif_export.paused;
+
+    if let Err(err) = window.await_capture_frame_jobs() {
+        eprintln!("Failed to export gif: {err:?}");
+        return;
+    }
+    match encode(&model.gif_export) {
+        Ok(frames) => println!("Exported {frames} frames to {}", model.gif_export.path),
+        Err(err) => eprintln!("Failed to export gif: {err}"),
+    }
+    if let Err(err) = std::fs::remove_dir_all(FRAME_DIRECTORY) {
+        eprintln!("Failed to remove {FRAME_DIRECTORY}: {err}");
+    }
+}
+
+// reads the captured png sequence and writes it as a gif with a quantized palette per frame
+fn encode(settings: &GifExport) -> Result<usize, Box<dyn Error>> {
+    let (width, height) = (settings.width as u16, settings.height as u16);
+    let mut encoder = gif::Encoder::new(File::create(&settings.path)?, width, height, &[])?;
+    encoder.set_repeat(if settings.loop_forever { gif::Repeat::Infinite } else { gif::Repeat::Finite(settings.loop_count) })?;
+    // the delay is given in hundredths of a second
+    let delay = (100.0 / settings.fps).round() as u16;
+
+    let mut frames = 0;
+    loop {
+        let path = Path::new(FRAME_DIRECTORY).join(format!("frame_{frames:05}.png"));
+        if !path.exists() {
+            break;
+        }
+        // the window may not have the exact size that was requested, e.g. on high dpi screens
+        let image = image::open(&path)?.to_rgba8();
+        let mut pixels = image::imageops::resize(&image, width as u32, height as u32, FilterType::Triangle).into_raw();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, settings.quantization_speed);
+        frame.delay = delay;
+        encoder.write_frame(&frame)?;
+        frames += 1;
+    }
+    Ok(frames)
+}
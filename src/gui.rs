@@ -1,46 +1,285 @@
-use nannou::{color::srgb, event::Update, glam::Vec2};
-use nannou_egui::egui::{self, Checkbox};
+use nannou::{color::srgb, event::Update};
+use nannou_egui::egui::{self, Checkbox, Color32};
 
-use crate::{light::Light, scene::{Scene, Scenes}, Model};
+use crate::{braitenberg_vehicle::{Vehicle, VehicleType}, camera::CameraMode, comparison::{self, Parameter, MAX_COPIES}, ecosystem::Ecosystem, editor::Tool, gif_export, history::{self, Command, Parameters}, light::Light, replay, rewind, snapshot::Snapshot, scene::Scenes, selection::Selected, telemetry::Format, viewports::Layout, Model};
 
 pub fn update_gui(model: &mut Model, update: Update) {
     model.egui.set_elapsed_time(update.since_start);
+    let sensor_values = match model.selection.selected {
+        Some(Selected::Vehicle(id)) => model.vehicles.iter()
+            .find(|vehicle| vehicle.id == id)
+            .map(|vehicle| vehicle.read_sensors(model.get_lights(), &model.neighbors())),
+        _ => None,
+    };
+    let parameters = Parameters::read(model);
     let ctx = model.egui.begin_frame();
+    let mut reset_scene = false;
+    let mut save_scene = false;
+    let mut undo = false;
+    let mut redo = false;
+    let mut start_replay = false;
+    let mut stop_replay = false;
+    let mut load_replay = false;
+    let mut exit_playback = false;
+    let mut rewind_now = false;
+    let mut export_gif = false;
+    let mut start_comparison = false;
     egui::Window::new("Settings").show(&ctx, |ui: &mut egui::Ui| {
         ui.heading("Settings:");
         ui.label("Select Scene:");
         nannou_egui::egui::ComboBox::from_label("")
             .selected_text(model.current_scene.to_str())
             .show_ui(ui, |ui|{
-                for scene in Scenes::Scene1 as u8..=Scenes::Scene7 as u8 {
+                for scene in Scenes::Scene1 as u8..=Scenes::Scene10 as u8 {
                     let scene = unsafe { std::mem::transmute(scene) };
                     ui.selectable_value(&mut model.current_scene, scene, scene.to_str());
                 }
             });
-        if ui.add(egui::Button::new("Reset Scene")).clicked() {
-            let scene = Scene::load_scene(model.current_scene);
-            model.vehicles = scene.vehicles;
-            let mouse_light = Light::new(Vec2::ZERO, srgb(1.0, 1.0, 1.0), 0.7);
-            model.lights = vec![mouse_light];
-            model.lights.extend(scene.lights);
+        if ui.add_enabled(model.replays.playback.is_none(), egui::Button::new("Reset Scene")).clicked() {
+            reset_scene = true;
         }
         ui.add(Checkbox::new(&mut model.show_controls, "Show Controls"));
-        ui.add(Checkbox::new(&mut model.follow_vehicle, "Follow Vehicle"));
-        if model.follow_vehicle && model.vehicles.len() > 1 {
-            ui.label("Select Vehicle:");
-            ui.horizontal(|ui| {
-                for i in 0..model.vehicles.len() {
-                    if ui.add(egui::Button::new(format!("{i}"))).clicked() {
-                        model.follow_vehicle_indx = i;
-                    }                    
+        ui.add(Checkbox::new(&mut model.show_history, "Show History"));
+        ui.add(Checkbox::new(&mut model.plots.enabled, "Show Plots"));
+        ui.add(Checkbox::new(&mut model.profiler.show_hud, "Show Performance HUD (F3)"));
+        ui.add(Checkbox::new(&mut model.profiler.show_graph, "Show Performance Graph"));
+        if ui.add(Checkbox::new(&mut model.editor.enabled, "Edit Mode")).changed() && model.editor.enabled {
+            model.paused = true;
+        }
+        ui.label("Viewports:");
+        egui::ComboBox::from_id_source("layout")
+            .selected_text(model.viewports.layout.to_str())
+            .show_ui(ui, |ui| {
+                for layout in Layout::ALL {
+                    ui.selectable_value(&mut model.viewports.layout, layout, layout.to_str());
                 }
             });
+        let vehicle_count = model.vehicles.len();
+        camera_controls(ui, 0, &mut model.camera_mode, &mut model.follow_vehicle_indx, vehicle_count);
+        for (i, view) in model.viewports.views.iter_mut().enumerate() {
+            ui.label(format!("Viewport {}:", i + 2));
+            camera_controls(ui, i + 1, &mut view.camera_mode, &mut view.follow_vehicle_indx, vehicle_count);
         }
         ui.add(Checkbox::new(&mut model.mouse_light, "Show a light at Mouse Position"));
         ui.label("Simulation Speed:");
         ui.add(egui::Slider::new(&mut model.simulation_speed, 1..=100).logarithmic(true));
         ui.add(Checkbox::new(&mut model.paused, "Pause Simulation"));
         ui.add(Checkbox::new(&mut model.draw_background, "Show background grid"));
+        ui.add(Checkbox::new(&mut model.minimap.enabled, "Show Minimap"));
+        ui.add(Checkbox::new(&mut model.ecosystem.enabled, "Ecosystem Mode"));
+        ui.add(Checkbox::new(&mut model.trails.settings.enabled, "Show Trails"));
+        if model.trails.settings.enabled {
+            ui.label("Trail Length:");
+            ui.add(egui::Slider::new(&mut model.trails.settings.length, 10..=5000).logarithmic(true));
+            ui.horizontal(|ui| {
+                ui.add(Checkbox::new(&mut model.trails.settings.fade, "Fade out"));
+                ui.add(Checkbox::new(&mut model.trails.settings.persistent, "Persistent trace"));
+            });
+            ui.label("Trail Width:");
+            ui.add(egui::Slider::new(&mut model.trails.settings.weight, 0.5..=20.0));
+            ui.label("Trail Opacity:");
+            ui.add(egui::Slider::new(&mut model.trails.settings.opacity, 0.05..=1.0));
+            let settings = &mut model.trails.settings;
+            let mut color = [settings.color.red, settings.color.green, settings.color.blue];
+            ui.horizontal(|ui| {
+                ui.add(Checkbox::new(&mut settings.color_by_type, "Color by vehicle type"));
+                if !settings.color_by_type && ui.color_edit_button_rgb(&mut color).changed() {
+                    settings.color = srgb(color[0], color[1], color[2]);
+                }
+            });
+            ui.text_edit_singleline(&mut model.trails.settings.export_path);
+            ui.horizontal(|ui| {
+                if ui.button("Clear Trails").clicked() {
+                    model.trails.clear();
+                }
+                if ui.button("Export SVG").clicked() {
+                    if let Err(err) = model.trails.export_svg() {
+                        eprintln!("Failed to export trails to {}: {err}", model.trails.settings.export_path);
+                    }
+                }
+            });
+        }
+        ui.add(Checkbox::new(&mut model.heatmap.enabled, "Show light field heatmap"));
+        if model.heatmap.enabled {
+            ui.horizontal(|ui| {
+                ui.add(Checkbox::new(&mut model.heatmap.log_scale, "Logarithmic"));
+                ui.add(Checkbox::new(&mut model.heatmap.show_contours, "Contour lines"));
+            });
+            if model.heatmap.show_contours {
+                ui.horizontal(|ui| {
+                    ui.label("Levels:");
+                    ui.text_edit_singleline(&mut model.heatmap.contour_levels);
+                });
+            }
+        }
+        ui.add(Checkbox::new(&mut model.behavior_field.enabled, "Show behavior field"));
+        if model.behavior_field.enabled {
+            egui::ComboBox::from_label("Vehicle type")
+                .selected_text(model.behavior_field.vehicle_type.to_str())
+                .show_ui(ui, |ui| {
+                    for vehicle_type in VehicleType::ALL {
+                        ui.selectable_value(&mut model.behavior_field.vehicle_type, vehicle_type, vehicle_type.to_str());
+                    }
+                });
+            ui.label("Heading:");
+            ui.drag_angle(&mut model.behavior_field.heading);
+        }
+        ui.add(Checkbox::new(&mut model.spatial_acceleration, "Spatial acceleration"));
+        if model.spatial_acceleration {
+            ui.label("Far-field approximation (0 = exact):");
+            ui.add(egui::Slider::new(&mut model.far_field_theta, 0.0..=1.0));
+        }
+        ui.label("Telemetry:");
+        if model.telemetry.is_recording() {
+            ui.label(format!("Recording to {} ({} samples)", model.telemetry.path, model.telemetry.frames()));
+            if ui.button("Stop Recording").clicked() {
+                model.telemetry.stop();
+            }
+        } else {
+            ui.horizontal(|ui| {
+                for format in [Format::Csv, Format::Jsonl] {
+                    if ui.selectable_value(&mut model.telemetry.format, format, format.to_str()).clicked() {
+                        let stem = model.telemetry.path.rsplit_once('.').map_or(model.telemetry.path.as_str(), |(stem, _)| stem);
+                        model.telemetry.path = format!("{stem}.{}", format.extension());
+                    }
+                }
+            });
+            ui.text_edit_singleline(&mut model.telemetry.path);
+            ui.label("Sampling Interval (s):");
+            ui.add(egui::Slider::new(&mut model.telemetry.interval, 0.0..=5.0));
+            if ui.button("Start Recording").clicked() {
+                if let Err(err) = model.telemetry.start() {
+                    eprintln!("Failed to start recording to {}: {err}", model.telemetry.path);
+                }
+            }
+        }
+        ui.add(Checkbox::new(&mut model.rewind.enabled, "Rewind Buffer"));
+        if model.rewind.enabled {
+            ui.label("Buffer Length (s):");
+            ui.add(egui::Slider::new(&mut model.rewind.duration, 5.0..=300.0).logarithmic(true));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut model.rewind.seconds).clamp_range(0.0..=model.rewind.duration).speed(0.1).suffix(" s"));
+                let button = egui::Button::new("Rewind");
+                if ui.add_enabled(model.replays.playback.is_none(), button).clicked() {
+                    rewind_now = true;
+                }
+            });
+            ui.label(format!("{:.1}s available", model.rewind.available()));
+        }
+        ui.label("A/B Comparison:");
+        if model.comparison.is_active() {
+            if ui.button("Stop Comparison").clicked() {
+                model.comparison.stop();
+            }
+        } else {
+            let comparison = &mut model.comparison;
+            egui::ComboBox::from_id_source("comparison_parameter")
+                .selected_text(comparison.parameter.to_str())
+                .show_ui(ui, |ui| {
+                    for parameter in Parameter::ALL {
+                        ui.selectable_value(&mut comparison.parameter, parameter, parameter.to_str());
+                    }
+                });
+            ui.add(egui::Slider::new(&mut comparison.copies, 2..=MAX_COPIES).text("Copies"));
+            for copy in 0..comparison.copies {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", (b'A' + copy as u8) as char));
+                    match comparison.parameter {
+                        Parameter::VehicleType => {
+                            for vehicle_type in VehicleType::ALL {
+                                ui.selectable_value(&mut comparison.vehicle_types[copy], vehicle_type, vehicle_type.to_str());
+                            }
+                        }
+                        Parameter::SensorGain => {
+                            ui.add(egui::Slider::new(&mut comparison.gains[copy], 0.1..=10.0).logarithmic(true));
+                        }
+                        Parameter::Falloff => {
+                            ui.add(egui::Slider::new(&mut comparison.falloffs[copy], 1.0..=4.0));
+                        }
+                    }
+                });
+            }
+            if ui.add_enabled(model.replays.playback.is_none() && !model.gif_export.is_exporting(), egui::Button::new("Start Comparison")).clicked() {
+                start_comparison = true;
+            }
+        }
+        ui.label("Replay:");
+        if model.replays.is_recording() {
+            ui.label(format!("Recording replay ({} frames)", model.replays.frames()));
+            if ui.button("Stop and Save Replay").clicked() {
+                stop_replay = true;
+            }
+        } else if model.replays.playback.is_none() {
+            ui.text_edit_singleline(&mut model.replays.path);
+            ui.horizontal(|ui| {
+                if ui.button("Record Replay").clicked() {
+                    start_replay = true;
+                }
+                if ui.button("Load Replay").clicked() {
+                    load_replay = true;
+                }
+            });
+        }
+        ui.label("Frame Capture:");
+        if model.capture.is_capturing() {
+            ui.label(format!("Saved {} frames to {}", model.capture.frames(), model.capture.directory));
+            if ui.button("Stop Capture").clicked() {
+                model.capture.stop();
+            }
+        } else {
+            ui.text_edit_singleline(&mut model.capture.directory);
+            ui.horizontal(|ui| {
+                ui.label("Resolution:");
+                ui.add(egui::DragValue::new(&mut model.capture.width).clamp_range(16..=7680));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut model.capture.height).clamp_range(16..=4320));
+            });
+            ui.label("Frames per Second:");
+            ui.add(egui::Slider::new(&mut model.capture.fps, 1.0..=120.0));
+            ui.label("Simulated Seconds per Video Second:");
+            ui.add(egui::Slider::new(&mut model.capture.time_scale, 0.1..=100.0).logarithmic(true));
+            ui.add(Checkbox::new(&mut model.capture.offline, "Offline Render"));
+            ui.add(Checkbox::new(&mut model.capture.hide_gui, "Hide GUI while capturing (F12 stops)"));
+            if ui.button("Start Capture").clicked() {
+                if let Err(err) = model.capture.start() {
+                    eprintln!("Failed to start capture to {}: {err}", model.capture.directory);
+                }
+            }
+        }
+        ui.label("GIF Export:");
+        if model.gif_export.is_exporting() {
+            ui.label(format!("Rendering frame {}", model.capture.frames()));
+        } else if !model.capture.is_capturing() {
+            ui.text_edit_singleline(&mut model.gif_export.path);
+            ui.horizontal(|ui| {
+                ui.label("Resolution:");
+                ui.add(egui::DragValue::new(&mut model.gif_export.width).clamp_range(16..=1920));
+                ui.label("x");
+                ui.add(egui::DragValue::new(&mut model.gif_export.height).clamp_range(16..=1080));
+            });
+            ui.label("Frames per Second:");
+            ui.add(egui::Slider::new(&mut model.gif_export.fps, 1.0..=50.0));
+            ui.label("Quantization Speed (1 = best colors):");
+            ui.add(egui::Slider::new(&mut model.gif_export.quantization_speed, 1..=30));
+            ui.horizontal(|ui| {
+                ui.add(Checkbox::new(&mut model.gif_export.loop_forever, "Loop forever"));
+                if !model.gif_export.loop_forever {
+                    ui.add(egui::DragValue::new(&mut model.gif_export.loop_count).clamp_range(1..=100).suffix(" times"));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Last");
+                ui.add(egui::DragValue::new(&mut model.gif_export.seconds).clamp_range(0.5..=model.rewind.duration).speed(0.1).suffix(" s"));
+                let button = egui::Button::new("Export GIF");
+                let enabled = model.rewind.enabled && model.replays.playback.is_none() && !model.comparison.is_active();
+                if ui.add_enabled(enabled, button).clicked() {
+                    export_gif = true;
+                }
+            });
+            if !model.rewind.enabled {
+                ui.weak("GIF export renders from the rewind buffer.");
+            }
+        }
         ui.label(format!("Camera Position: ({:.0}, {:.0})", model.camera.position.x, model.camera.position.y));
         ui.label(format!("Camera Zoom: {}", model.camera.zoom));            
     });
@@ -49,9 +288,318 @@ pub fn update_gui(model: &mut Model, update: Update) {
         egui::Window::new("Controlls").show(&ctx, |ui| {
             ui.label("- Right click and hold to move the camera.");
             ui.label("- Scroll to zoom in and out.");
+            ui.label("- Press C to cycle through the camera modes.");
+            ui.label("- Press R to rewind the simulation.");
+            ui.label("- Press F12 to start or stop the frame capture.");
+            ui.label("- Press F3 to show or hide the performance HUD.");
             if ui.button("Close").clicked() {
                 model.show_controls = false;
             }
         });
     }
-}
\ No newline at end of file
+
+    if let Some(playback) = &mut model.replays.playback {
+        egui::Window::new("Playback").show(&ctx, |ui| {
+            ui.label(format!("Seed {}, frame {} of {}", playback.seed(), playback.frame() + 1, playback.frame_count()));
+            let duration = playback.duration();
+            ui.add(egui::Slider::new(&mut playback.time, 0.0..=duration).text("Time (s)"));
+            ui.horizontal(|ui| {
+                if ui.button("<").clicked() {
+                    playback.step(-1);
+                }
+                let label = if playback.playing { "Pause" } else { "Play" };
+                if ui.button(label).clicked() {
+                    if !playback.playing && playback.time >= duration {
+                        playback.time = 0.0;
+                    }
+                    playback.playing = !playback.playing;
+                }
+                if ui.button(">").clicked() {
+                    playback.step(1);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Jump to (s):");
+                ui.add(egui::DragValue::new(&mut playback.time).clamp_range(0.0..=duration).speed(0.1));
+            });
+            ui.label("Playback Speed:");
+            ui.add(egui::Slider::new(&mut playback.speed, 0.1..=10.0).logarithmic(true));
+            if ui.button("Exit Playback").clicked() {
+                exit_playback = true;
+            }
+        });
+    }
+
+    if model.plots.enabled {
+        egui::Window::new("Plots").show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Window (s):");
+                ui.add(egui::Slider::new(&mut model.plots.window, 1.0..=60.0));
+            });
+            model.plots.draw(ui);
+            ui.text_edit_singleline(&mut model.plots.export_path);
+            if ui.button("Export CSV").clicked() {
+                if let Err(err) = model.plots.export_csv() {
+                    eprintln!("Failed to export plot to {}: {err}", model.plots.export_path);
+                }
+            }
+        });
+    }
+
+    if model.profiler.show_graph {
+        egui::Window::new("Performance").show(&ctx, |ui| {
+            model.profiler.draw_graph(ui);
+        });
+    }
+
+    if model.show_history {
+        egui::Window::new("History").show(&ctx, |ui| {
+            ui.horizontal(|ui| {
+                // the history belongs to the live world, not to the frames of a replay
+                let enabled = model.replays.playback.is_none();
+                undo = ui.add_enabled(enabled, egui::Button::new("Undo (Ctrl+Z)")).clicked();
+                redo = ui.add_enabled(enabled, egui::Button::new("Redo (Ctrl+Shift+Z)")).clicked();
+            });
+            egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                for description in model.history.undo_descriptions() {
+                    ui.label(description);
+                }
+                for description in model.history.redo_descriptions() {
+                    ui.weak(description);
+                }
+            });
+        });
+    }
+
+    if model.editor.enabled {
+        egui::Window::new("Editor").show(&ctx, |ui| {
+            ui.label("Place:");
+            ui.horizontal(|ui| {
+                let tools = [Tool::Select].into_iter()
+                    .chain(VehicleType::ALL.map(Tool::Vehicle))
+                    .chain([Tool::Light]);
+                for tool in tools {
+                    ui.selectable_value(&mut model.editor.tool, tool, tool.to_str());
+                }
+            });
+            ui.add(Checkbox::new(&mut model.editor.snap_to_grid, "Snap to grid"));
+            ui.label("- Drag the handle in front of a vehicle to rotate it.");
+            ui.label("- Press Delete to remove the selected object.");
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut model.editor.scene_path);
+                if ui.button("Save Scene").clicked() {
+                    save_scene = true;
+                }
+            });
+        });
+    }
+
+    if let Some(selected) = model.selection.selected {
+        let mut open = true;
+        egui::Window::new("Inspector").open(&mut open).show(&ctx, |ui| {
+            match selected {
+                Selected::Vehicle(id) => {
+                    if let Some(vehicle) = model.vehicles.iter_mut().find(|vehicle| vehicle.id == id) {
+                        vehicle_inspector(ui, vehicle, sensor_values.unwrap_or_default());
+                    }
+                }
+                Selected::Light(index) => {
+                    if let Some(light) = model.lights.get_mut(index) {
+                        light_inspector(ui, light);
+                    }
+                }
+            }
+        });
+        if !open {
+            model.selection.clear();
+        }
+    }
+
+    if model.ecosystem.enabled {
+        egui::Window::new("Population").show(&ctx, |ui| {
+            draw_population_chart(ui, &model.ecosystem);
+            ui.text_edit_singleline(&mut model.ecosystem.export_path);
+            if ui.button("Export History").clicked() {
+                if let Err(err) = model.ecosystem.export_history(&model.ecosystem.export_path) {
+                    eprintln!("Failed to export population history to {}: {err}", model.ecosystem.export_path);
+                }
+            }
+        });
+    }
+
+    let pointer_down = ctx.input(|input| input.pointer.any_down());
+    // the frame context borrows the egui state of the model, end the frame before touching the whole model
+    drop(ctx);
+
+    let changed_parameters = Parameters::read(model);
+    if changed_parameters != parameters {
+        model.history.push_parameters(parameters, changed_parameters);
+    }
+    if !pointer_down {
+        model.history.end_parameter_gesture();
+    }
+    if reset_scene {
+        let before = (Snapshot::capture(model), model.ecosystem.clone());
+        model.load_from_file(model.current_scene, false);
+        let after = (Snapshot::capture(model), model.ecosystem.clone());
+        model.history.push(Command::ResetScene { before: Box::new(before), after: Box::new(after) });
+    }
+    if rewind_now {
+        rewind::rewind(model);
+    }
+    if export_gif {
+        gif_export::start(model);
+    }
+    if start_comparison {
+        comparison::start(model);
+    }
+    if start_replay {
+        replay::start_recording(model);
+    }
+    if stop_replay {
+        if let Err(err) = replay::stop_recording(model) {
+            eprintln!("Failed to save replay: {err}");
+        }
+    }
+    if load_replay {
+        if let Err(err) = replay::start_playback(model) {
+            eprintln!("Failed to load replay: {err}");
+        }
+    }
+    if exit_playback {
+        replay::stop_playback(model);
+    }
+    if undo {
+        history::undo(model);
+    }
+    if redo {
+        history::redo(model);
+    }
+    if save_scene {
+        if let Err(err) = model.to_scene().save_scene(&model.editor.scene_path) {
+            eprintln!("Failed to save scene to {}: {err}", model.editor.scene_path);
+        }
+    }
+}
+
+fn vehicle_inspector(ui: &mut egui::Ui, vehicle: &mut Vehicle, sensor_values: (f32, f32)) {
+    ui.label(format!("Vehicle {}", vehicle.id));
+    let mut vehicle_type = vehicle.vehicle_type();
+    egui::ComboBox::from_label("Type")
+        .selected_text(vehicle_type.to_str())
+        .show_ui(ui, |ui| {
+            for option in VehicleType::ALL {
+                ui.selectable_value(&mut vehicle_type, option, option.to_str());
+            }
+        });
+    vehicle.set_vehicle_type(vehicle_type);
+    ui.horizontal(|ui| {
+        ui.label("Team:");
+        ui.add(egui::DragValue::new(&mut vehicle.team));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Position:");
+        ui.add(egui::DragValue::new(&mut vehicle.position.x).speed(5.0));
+        ui.add(egui::DragValue::new(&mut vehicle.position.y).speed(5.0));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Orientation:");
+        ui.drag_angle(&mut vehicle.orientation);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Velocity:");
+        ui.add(egui::DragValue::new(&mut vehicle.velocity));
+    });
+    ui.horizontal(|ui| {
+        ui.label("Energy:");
+        ui.add(egui::DragValue::new(&mut vehicle.energy));
+    });
+    ui.label(format!("Sensors: left {:.3}, right {:.3}", sensor_values.0, sensor_values.1));
+}
+
+fn light_inspector(ui: &mut egui::Ui, light: &mut Light) {
+    ui.label("Light");
+    ui.horizontal(|ui| {
+        ui.label("Position:");
+        ui.add(egui::DragValue::new(&mut light.position.x).speed(5.0));
+        ui.add(egui::DragValue::new(&mut light.position.y).speed(5.0));
+    });
+    ui.label("Intensity:");
+    ui.add(egui::Slider::new(&mut light.intensity, 0.0..=2.0));
+    let mut color = [light.color.red, light.color.green, light.color.blue];
+    ui.horizontal(|ui| {
+        ui.label("Color:");
+        if ui.color_edit_button_rgb(&mut color).changed() {
+            light.color = srgb(color[0], color[1], color[2]);
+        }
+    });
+}
+
+// draws one line per species showing its population over time
+fn draw_population_chart(ui: &mut egui::Ui, ecosystem: &Ecosystem) {
+    let (response, painter) = ui.allocate_painter(egui::vec2(300.0, 150.0), egui::Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+
+    let history = &ecosystem.history;
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return;
+    };
+    let duration = (last.time - first.time).max(f32::EPSILON);
+    let max_population = history.iter()
+        .flat_map(|sample| sample.populations.values().copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    for species in ecosystem.species() {
+        let points = history.iter()
+            .map(|sample| {
+                let population = sample.populations.get(&species).copied().unwrap_or(0);
+                egui::pos2(
+                    rect.left() + (sample.time - first.time) / duration * rect.width(),
+                    rect.bottom() - population as f32 / max_population as f32 * rect.height(),
+                )
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, species_color(species))));
+    }
+
+    let species_labels: Vec<String> = ecosystem.species().iter()
+        .map(|species| format!("{species}: {}", last.populations.get(species).copied().unwrap_or(0)))
+        .collect();
+    ui.label(format!("Population ({max_population} max): {}", species_labels.join(", ")));
+}
+
+pub fn species_color(species: u32) -> Color32 {
+    const COLORS: [Color32; 6] = [
+        Color32::from_rgb(255, 255, 0),
+        Color32::from_rgb(255, 0, 255),
+        Color32::from_rgb(0, 255, 255),
+        Color32::from_rgb(0, 255, 0),
+        Color32::from_rgb(255, 128, 0),
+        Color32::from_rgb(80, 80, 255),
+    ];
+    COLORS[species as usize % COLORS.len()]
+}
+
+fn camera_controls(ui: &mut egui::Ui, id: usize, camera_mode: &mut CameraMode, follow_vehicle_indx: &mut usize, vehicle_count: usize) {
+    ui.label("Camera Mode:");
+    egui::ComboBox::from_id_source(("camera_mode", id))
+        .selected_text(camera_mode.to_str())
+        .show_ui(ui, |ui| {
+            for mode in CameraMode::ALL {
+                ui.selectable_value(camera_mode, mode, mode.to_str());
+            }
+        });
+    if camera_mode.follows_vehicle() && vehicle_count > 1 {
+        ui.label("Select Vehicle:");
+        ui.horizontal_wrapped(|ui| {
+            for i in 0..vehicle_count {
+                if ui.selectable_label(*follow_vehicle_indx == i, format!("{i}")).clicked() {
+                    *follow_vehicle_indx = i;
+                }
+            }
+        });
+    }
+}
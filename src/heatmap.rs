@@ -0,0 +1,118 @@
+use nannou::{color::{srgba, Srgba}, geom::{self, Rect}, glam::Vec2};
+use rayon::prelude::*;
+
+use crate::{braitenberg_vehicle::sensor_response, camera::Camera, spatial::LightSource};
+
+pub struct Heatmap {
+    pub enabled: bool,
+    // size of one heatmap cell on screen
+    pub cell_size: f32,
+    // maps the sensor value logarithmically, otherwise only the area close to lights is visible
+    pub log_scale: bool,
+    pub show_contours: bool,
+    // comma separated sensor values to draw contour lines at
+    pub contour_levels: String,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Heatmap {
+            enabled: false,
+            cell_size: 12.0,
+            log_scale: true,
+            show_contours: true,
+            contour_levels: String::from("0.05, 0.1, 0.25, 0.5, 0.9"),
+        }
+    }
+
+    fn levels(&self) -> Vec<f32> {
+        self.contour_levels.split(',')
+            .filter_map(|level| level.trim().parse().ok())
+            .collect()
+    }
+
+    // draws the value a light sensor would read at every point of the window, returns the number of primitives
+    pub fn draw<L: LightSource + ?Sized>(&self, draw: &nannou::draw::Draw, camera: &Camera, window: Rect, lights: &L) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let columns = (window.w() / self.cell_size).ceil() as usize + 1;
+        let rows = (window.h() / self.cell_size).ceil() as usize + 1;
+        let origin = Vec2::new(window.left(), window.bottom());
+        let screen_pos = |column: usize, row: usize| origin + Vec2::new(column as f32, row as f32) * self.cell_size;
+
+        // sensor values at the corners of the cells, row by row
+        let values: Vec<f32> = (0..rows * columns)
+            .into_par_iter()
+            .map(|i| {
+                let world_pos = screen_pos(i % columns, i / columns) / camera.zoom + camera.position;
+                sensor_response(lights.field_at(world_pos))
+            })
+            .collect();
+        let value = |column: usize, row: usize| values[row * columns + column];
+
+        let tris = (0..rows - 1).flat_map(|row| (0..columns - 1).map(move |column| (column, row)))
+            .flat_map(|(column, row)| {
+                let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)]
+                    .map(|(c, r)| (screen_pos(c, r).extend(0.0), self.color(value(c, r))));
+                [
+                    geom::Tri([corners[0], corners[1], corners[2]]),
+                    geom::Tri([corners[0], corners[2], corners[3]]),
+                ]
+            });
+        draw.mesh().tris_colored(tris);
+
+        if !self.show_contours {
+            return 1;
+        }
+        let mut lines = 0;
+        for level in self.levels() {
+            for row in 0..rows - 1 {
+                for column in 0..columns - 1 {
+                    let corners = [(column, row), (column + 1, row), (column + 1, row + 1), (column, row + 1)];
+                    let crossings = contour_crossings(corners.map(|(c, r)| (screen_pos(c, r), value(c, r))), level);
+                    for segment in crossings.chunks_exact(2) {
+                        draw.line()
+                            .start(segment[0])
+                            .end(segment[1])
+                            .stroke_weight(1.0)
+                            .color(srgba(1.0, 1.0, 1.0, 0.5));
+                        lines += 1;
+                    }
+                }
+            }
+        }
+        1 + lines
+    }
+
+    fn color(&self, value: f32) -> Srgba {
+        let t = if self.log_scale {
+            ((value * 100.0).max(1.0).log10() / 2.0).clamp(0.0, 1.0)
+        } else {
+            value.clamp(0.0, 1.0)
+        };
+        // black -> purple -> orange -> yellow
+        let (r, g, b) = if t < 0.5 {
+            let t = t * 2.0;
+            (0.5 * t, 0.0, 0.5 * t)
+        } else {
+            let t = (t - 0.5) * 2.0;
+            (0.5 + 0.5 * t, t, 0.5 - 0.5 * t)
+        };
+        srgba(r, g, b, 0.6)
+    }
+}
+
+// marching squares for a single cell, returns pairs of points where the contour crosses the cell edges
+fn contour_crossings(corners: [(Vec2, f32); 4], level: f32) -> Vec<Vec2> {
+    let mut crossings = Vec::new();
+    for i in 0..4 {
+        let (a, a_value) = corners[i];
+        let (b, b_value) = corners[(i + 1) % 4];
+        if (a_value < level) != (b_value < level) {
+            let t = (level - a_value) / (b_value - a_value);
+            crossings.push(a.lerp(b, t));
+        }
+    }
+    crossings
+}
@@ -0,0 +1,248 @@
+use crate::{braitenberg_vehicle::Vehicle, ecosystem::Ecosystem, light::Light, selection::Selected, snapshot::Snapshot, Model};
+
+// settings of the model that can be undone
+#[derive(Clone, Copy, PartialEq)]
+pub struct Parameters {
+    pub simulation_speed: u32,
+    pub mouse_light: bool,
+    pub spatial_acceleration: bool,
+    pub far_field_theta: f32,
+    pub ecosystem: bool,
+}
+
+impl Parameters {
+    pub fn read(model: &Model) -> Self {
+        Parameters {
+            simulation_speed: model.simulation_speed,
+            mouse_light: model.mouse_light,
+            spatial_acceleration: model.spatial_acceleration,
+            far_field_theta: model.far_field_theta,
+            ecosystem: model.ecosystem.enabled,
+        }
+    }
+
+    fn apply(&self, model: &mut Model) {
+        model.simulation_speed = self.simulation_speed;
+        model.mouse_light = self.mouse_light;
+        model.spatial_acceleration = self.spatial_acceleration;
+        model.far_field_theta = self.far_field_theta;
+        model.ecosystem.enabled = self.ecosystem;
+    }
+}
+
+pub enum Command {
+    AddVehicle(Vehicle),
+    RemoveVehicle { index: usize, vehicle: Vehicle },
+    EditVehicle { before: Vehicle, after: Vehicle },
+    // indices are into the lights of the model, including the mouse light
+    AddLight { index: usize, light: Light },
+    RemoveLight { index: usize, light: Light },
+    EditLight { index: usize, before: Light, after: Light },
+    SetParameters { before: Parameters, after: Parameters },
+    // the reset also replaces the ecosystem settings and clears the population history, which snapshots do not keep
+    ResetScene { before: Box<(Snapshot, Ecosystem)>, after: Box<(Snapshot, Ecosystem)> },
+    Rewind { seconds: f32, before: Box<Snapshot>, after: Box<Snapshot> },
+}
+
+impl Command {
+    pub fn description(&self) -> String {
+        match self {
+            Command::AddVehicle(vehicle) => format!("Add vehicle {}", vehicle.id),
+            Command::RemoveVehicle { vehicle, .. } => format!("Remove vehicle {}", vehicle.id),
+            Command::EditVehicle { after, .. } => format!("Edit vehicle {}", after.id),
+            Command::AddLight { .. } => String::from("Add light"),
+            Command::RemoveLight { .. } => String::from("Remove light"),
+            Command::EditLight { .. } => String::from("Edit light"),
+            Command::SetParameters { .. } => String::from("Change settings"),
+            Command::ResetScene { .. } => String::from("Reset scene"),
+            Command::Rewind { seconds, .. } => format!("Rewind {seconds:.1}s"),
+        }
+    }
+
+    fn apply(&self, model: &mut Model, undo: bool) {
+        match self {
+            Command::AddVehicle(vehicle) if undo => remove_vehicle(model, vehicle.id),
+            Command::AddVehicle(vehicle) => model.vehicles.push(vehicle.clone()),
+            Command::RemoveVehicle { index, vehicle } if undo => {
+                model.vehicles.insert((*index).min(model.vehicles.len()), vehicle.clone());
+            }
+            Command::RemoveVehicle { vehicle, .. } => remove_vehicle(model, vehicle.id),
+            Command::EditVehicle { before, after } => {
+                let state = if undo { before } else { after };
+                if let Some(vehicle) = model.vehicles.iter_mut().find(|vehicle| vehicle.id == state.id) {
+                    *vehicle = state.clone();
+                }
+            }
+            Command::AddLight { index, .. } if undo => remove_light(model, *index),
+            Command::AddLight { index, light } => insert_light(model, *index, light),
+            Command::RemoveLight { index, light } if undo => insert_light(model, *index, light),
+            Command::RemoveLight { index, .. } => remove_light(model, *index),
+            Command::EditLight { index, before, after } => {
+                if let Some(light) = model.lights.get_mut(*index) {
+                    *light = if undo { before.clone() } else { after.clone() };
+                }
+            }
+            Command::SetParameters { before, after } => {
+                if undo { before.apply(model) } else { after.apply(model) }
+            }
+            Command::ResetScene { before, after } => {
+                let (snapshot, ecosystem) = if undo { before.as_ref() } else { after.as_ref() };
+                snapshot.restore(model);
+                model.ecosystem = ecosystem.clone();
+            }
+            Command::Rewind { before, after, .. } => {
+                if undo { before.restore(model) } else { after.restore(model) }
+            }
+        }
+    }
+
+    // commands that add or remove objects shift the indices the selection refers to
+    fn changes_structure(&self) -> bool {
+        !matches!(self, Command::EditVehicle { .. } | Command::EditLight { .. } | Command::SetParameters { .. })
+    }
+}
+
+fn remove_vehicle(model: &mut Model, id: usize) {
+    model.vehicles.retain(|vehicle| vehicle.id != id);
+}
+
+fn insert_light(model: &mut Model, index: usize, light: &Light) {
+    model.lights.insert(index.clamp(1, model.lights.len()), light.clone());
+}
+
+fn remove_light(model: &mut Model, index: usize) {
+    if index > 0 && index < model.lights.len() {
+        model.lights.remove(index);
+    }
+}
+
+// last committed state of the selected object, edits are recorded against it
+#[derive(Clone, PartialEq)]
+enum Tracked {
+    Vehicle(Vehicle),
+    Light(usize, Light),
+}
+
+impl Tracked {
+    fn capture(model: &Model) -> Option<Self> {
+        match model.selection.selected? {
+            Selected::Vehicle(id) => model.vehicles.iter()
+                .find(|vehicle| vehicle.id == id)
+                .map(|vehicle| Tracked::Vehicle(vehicle.clone())),
+            Selected::Light(index) => model.lights.get(index).map(|light| Tracked::Light(index, light.clone())),
+        }
+    }
+
+    fn same_object(&self, other: &Tracked) -> bool {
+        match (self, other) {
+            (Tracked::Vehicle(a), Tracked::Vehicle(b)) => a.id == b.id,
+            (Tracked::Light(a, _), Tracked::Light(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+    tracked: Option<Tracked>,
+    // consecutive parameter changes while a slider is dragged become a single command
+    parameter_gesture: bool,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            tracked: None,
+            parameter_gesture: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = History::new();
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.undo.push(command);
+        self.redo.clear();
+        self.parameter_gesture = false;
+    }
+
+    pub fn push_parameters(&mut self, before: Parameters, after: Parameters) {
+        if self.parameter_gesture {
+            if let Some(Command::SetParameters { after: last, .. }) = self.undo.last_mut() {
+                *last = after;
+                return;
+            }
+        }
+        self.push(Command::SetParameters { before, after });
+        self.parameter_gesture = true;
+    }
+
+    pub fn end_parameter_gesture(&mut self) {
+        self.parameter_gesture = false;
+    }
+
+    pub fn undo_descriptions(&self) -> impl Iterator<Item = String> + '_ {
+        self.undo.iter().map(Command::description)
+    }
+
+    pub fn redo_descriptions(&self) -> impl Iterator<Item = String> + '_ {
+        self.redo.iter().rev().map(Command::description)
+    }
+}
+
+pub fn undo(model: &mut Model) {
+    if let Some(command) = model.history.undo.pop() {
+        command.apply(model, true);
+        finish(model, &command);
+        model.history.redo.push(command);
+    }
+}
+
+pub fn redo(model: &mut Model) {
+    if let Some(command) = model.history.redo.pop() {
+        command.apply(model, false);
+        finish(model, &command);
+        model.history.undo.push(command);
+    }
+}
+
+fn finish(model: &mut Model, command: &Command) {
+    if command.changes_structure() {
+        model.selection.clear();
+    }
+    model.history.tracked = Tracked::capture(model);
+    model.history.parameter_gesture = false;
+}
+
+// records changes the user made to the selected object since the last call.
+// while a gesture like dragging is active the change is only recorded once it ends.
+pub fn track_selection(model: &mut Model, gesture_active: bool) {
+    let current = Tracked::capture(model);
+    if let (Some(before), Some(now)) = (&model.history.tracked, &current) {
+        if before.same_object(now) {
+            if gesture_active {
+                return;
+            }
+            if before != now {
+                let command = match (before.clone(), now.clone()) {
+                    (Tracked::Vehicle(before), Tracked::Vehicle(after)) => Command::EditVehicle { before, after },
+                    (Tracked::Light(index, before), Tracked::Light(_, after)) => Command::EditLight { index, before, after },
+                    _ => unreachable!(),
+                };
+                model.history.push(command);
+            }
+        }
+    }
+    model.history.tracked = current;
+}
+
+// takes over changes made by the simulation without recording them
+pub fn sync_selection(model: &mut Model, gesture_active: bool) {
+    if !gesture_active {
+        model.history.tracked = Tracked::capture(model);
+    }
+}
@@ -4,20 +4,87 @@ mod camera;
 mod scene;
 mod event_handlers;
 mod gui;
+mod ecosystem;
+mod environment;
+mod spatial;
+mod trails;
+mod heatmap;
+mod behavior_field;
+mod selection;
+mod editor;
+mod snapshot;
+mod history;
+mod plots;
+mod replay;
+mod rewind;
+mod telemetry;
+mod capture;
+mod gif_export;
+mod minimap;
+mod viewports;
+mod comparison;
+mod render;
+mod profiler;
+
+use std::time::{Duration, Instant};
 
 use nannou_egui::{self, Egui};
-use nannou::{color::srgb, event::Update, glam::Vec2, rand::random_range, App, Draw, Frame};
-use braitenberg_vehicle::Vehicle;
-use camera::Camera;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use nannou::{color::srgb, event::Update, geom::Rect, glam::Vec2, App, Draw, Frame};
+use braitenberg_vehicle::{Neighbor, Vehicle};
+use camera::{Camera, CameraMode};
+use ecosystem::Ecosystem;
+use environment::Environment;
 use light::Light;
 use scene::{Scene, Scenes};
+use spatial::{Falloff, LightSource, LightTree, SpatialGrid};
+use trails::Trails;
+use heatmap::Heatmap;
+use behavior_field::BehaviorField;
+use selection::{Selected, Selection};
+use editor::Editor;
+use history::History;
+use capture::Capture;
+use gif_export::GifExport;
+use minimap::Minimap;
+use plots::Plots;
+use replay::Replays;
+use rewind::Rewind;
+use telemetry::Telemetry;
+use viewports::Viewports;
+use comparison::Comparison;
+use profiler::{Pass, Profiler, Stage};
+
+// below this many vehicles the threading overhead outweighs the parallel update
+const PARALLEL_THRESHOLD: usize = 256;
+// squared distance at which a vehicle reaches a light
+const COLLISION_DISTANCE: f32 = 20000.0;
+// size of the background tiles
+const GRID_SIZE: f32 = 500.0;
+// world units kept around the vehicles by the fit all camera mode
+const FIT_ALL_MARGIN: f32 = 400.0;
 
 fn main() {
+    if std::env::args().any(|arg| arg == "--benchmark") {
+        spatial::run_benchmark();
+        return;
+    }
     nannou::app(Model::new)
         .update(Model::update)
+        .exit(Model::exit)
         .run();
 }
 
+// --seed <number> makes runs reproducible, otherwise every start uses a new seed
+fn seed_from_args() -> u64 {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter().position(|arg| arg == "--seed")
+        .and_then(|i| args.get(i + 1))
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random)
+}
+
 struct Model {
     pub egui: Egui,
     vehicles: Vec<Vehicle>,
@@ -27,11 +94,36 @@ struct Model {
     previous_scene: Scenes,
     show_controls: bool,
     simulation_speed: u32,
-    follow_vehicle: bool,
+    camera_mode: CameraMode,
     follow_vehicle_indx: usize,
     mouse_light: bool,
     draw_background: bool,
     paused: bool,
+    ecosystem: Ecosystem,
+    next_vehicle_id: usize,
+    environment: Environment,
+    spatial_acceleration: bool,
+    far_field_theta: f32,
+    trails: Trails,
+    heatmap: Heatmap,
+    behavior_field: BehaviorField,
+    selection: Selection,
+    editor: Editor,
+    history: History,
+    show_history: bool,
+    plots: Plots,
+    telemetry: Telemetry,
+    // every random decision of the simulation comes from the rng, it is reseeded whenever a scene is loaded
+    seed: u64,
+    rng: StdRng,
+    replays: Replays,
+    rewind: Rewind,
+    capture: Capture,
+    gif_export: GifExport,
+    minimap: Minimap,
+    viewports: Viewports,
+    comparison: Comparison,
+    profiler: Profiler,
 }
 
 impl Model {
@@ -56,89 +148,303 @@ impl Model {
             previous_scene: Scenes::Scene1,
             show_controls: true,
             simulation_speed: 1,
-            follow_vehicle: false,
+            camera_mode: CameraMode::Free,
             follow_vehicle_indx: 0,
             mouse_light: false,
             draw_background: true,
             paused: false,
+            ecosystem: Ecosystem::new(None),
+            next_vehicle_id: 0,
+            environment: Environment::default(),
+            spatial_acceleration: false,
+            far_field_theta: 0.5,
+            trails: Trails::new(),
+            heatmap: Heatmap::new(),
+            behavior_field: BehaviorField::new(),
+            selection: Selection::new(),
+            editor: Editor::new(),
+            history: History::new(),
+            show_history: false,
+            plots: Plots::new(),
+            telemetry: Telemetry::from_args(),
+            seed: seed_from_args(),
+            rng: StdRng::seed_from_u64(0),
+            replays: Replays::new(),
+            rewind: Rewind::new(),
+            capture: Capture::new(),
+            gif_export: GifExport::new(),
+            minimap: Minimap::new(),
+            viewports: Viewports::new(),
+            comparison: Comparison::new(),
+            profiler: Profiler::new(),
         };
-        model.load_from_file(Scenes::Scene1);
+        model.load_from_file(Scenes::Scene1, true);
         model
     }
 
     fn update(app: &App, model: &mut Self, update: Update) {
-        model.camera.update_pos(&app.mouse);
+        model.profiler.begin_frame(update.since_last);
+        gif_export::update(app, model);
+        model.capture.begin_frame(&app.main_window());
+        let viewports = model.update_viewports(app);
+        model.active_camera_mut().update_pos(&app.mouse);
+        // the world of the model is hidden while copies of it are compared
+        let pointer_over_gui = model.egui.ctx().wants_pointer_input() || model.comparison.is_active();
+        let over_minimap = minimap::update(app, model, viewports[0], pointer_over_gui);
+        if over_minimap && app.mouse.buttons.left().is_down() {
+            model.camera_mode = CameraMode::Free;
+        }
+        let pointer_over_gui = pointer_over_gui || over_minimap;
+        // a replay is only watched, edits would be overwritten by its next frame
+        if model.replays.playback.is_none() {
+            // objects can be edited in every viewport
+            let camera = model.active_camera().clone();
+            let click_used = editor::update(model, &app.mouse, &camera, pointer_over_gui);
+            model.selection.update(&app.mouse, &camera, &mut model.vehicles, &mut model.lights, pointer_over_gui || click_used);
+            model.editor.snap_selection(&model.selection, &mut model.vehicles, &mut model.lights);
+        }
         model.update_scene();
         model.update_mouse_light(app);
         gui::update_gui(model, update);
 
-        if model.paused {
+        if model.replays.playback.is_some() {
+            replay::update_playback(model, update.since_last.as_secs_f32());
+            model.update_cameras(&viewports, update.since_last.as_secs_f32());
             return;
         }
-    
-        for _ in 0..model.simulation_speed {
-            let lights = if model.mouse_light { &model.lights } else { &model.lights[1..] };
-            for vehicle in model.vehicles.iter_mut() {
-                vehicle.update(lights, update.since_last.as_secs_f32());
+
+        // edits made by dragging are recorded once the mouse button is released
+        let gesture_active = app.mouse.buttons.left().is_down();
+        history::track_selection(model, gesture_active);
+
+        if !model.paused {
+            model.step_simulation(update, gesture_active);
+        }
+        // the cameras keep moving while the simulation is paused
+        model.update_cameras(&viewports, update.since_last.as_secs_f32());
+    }
+
+    fn view(app: &App, model: &Self, frame: Frame) {
+        let screen_draw = app.draw();
+        screen_draw.background().color(srgb(0.0, 0.0, 0.0));
+
+        let viewports = model.viewports.layout.rects(app.window_rect());
+        if model.comparison.is_active() {
+            comparison::draw(model, &screen_draw, app.window_rect());
+        } else {
+            let cameras = std::iter::once(&model.camera).chain(model.viewports.views.iter().map(|view| &view.camera));
+            for (camera, viewport) in cameras.zip(&viewports) {
+                // everything in the world is clipped to the viewport and drawn rotated with its camera
+                let draw = screen_draw.scissor(*viewport).xy(viewport.xy()).rotate(-camera.rotation);
+                model.draw_world(&draw, camera, Rect::from_wh(viewport.wh()));
+            }
+        }
+        let profiler = &model.profiler;
+        if viewports.len() > 1 && !model.comparison.is_active() {
+            for viewport in &viewports {
+                screen_draw.rect()
+                    .xy(viewport.xy())
+                    .wh(viewport.wh())
+                    .no_fill()
+                    .stroke(srgb(0.3, 0.3, 0.3))
+                    .stroke_weight(2.0);
             }
-            model.replace_lights_on_collision();
         }
-        
-        if model.follow_vehicle {
-            model.camera.position = model.vehicles[model.follow_vehicle_indx].position;
+        if !model.capture.hides_gui() && !model.comparison.is_active() {
+            profiler.pass(Pass::Minimap, || {
+                model.minimap.draw(&screen_draw, &model.camera, app.window_rect(), viewports[0], &model.vehicles, &model.lights[1..])
+            });
+        }
+        if !model.capture.hides_gui() {
+            profiler.draw_hud(&screen_draw, app.window_rect(), model.simulation_speed, model.vehicles.len(), model.get_lights().len());
+        }
+
+        profiler.pass(Pass::Submit, || {
+            screen_draw.to_frame(app, &frame).unwrap();
+            0
+        });
+        if !model.capture.hides_gui() {
+            profiler.pass(Pass::Gui, || {
+                model.egui.draw_to_frame(&frame).unwrap();
+                0
+            });
+        }
+        if let Some(path) = model.capture.pending_frame() {
+            app.main_window().capture_frame(path);
         }
     }
 
-    fn view(app: &App, model: &Self, frame: Frame) {
-        let draw = app.draw();
-        
-        model.draw_background(&draw, app);
+    // captured frames are written in the background
+    fn exit(app: &App, _model: Self) {
+        if let Err(err) = app.main_window().await_capture_frame_jobs() {
+            eprintln!("Failed to write captured frames: {err:?}");
+        }
+    }
+
+    fn draw_world(&self, draw: &Draw, camera: &Camera, window: Rect) {
+        let view_rect = camera.view_rect(window);
+        let profiler = &self.profiler;
+        profiler.pass(Pass::Background, || self.draw_background(draw, camera, view_rect));
+        profiler.pass(Pass::Environment, || self.environment.draw(draw, camera, view_rect));
 
-        for light in model.get_lights() {
-            light.draw(&draw, &model.camera);
+        profiler.pass(Pass::Lights, || render::draw_lights(draw, camera, view_rect, &Falloff::new(self.get_lights())));
+
+        profiler.pass(Pass::Overlays, || {
+            self.heatmap.draw(draw, camera, view_rect, self.get_lights())
+                + self.behavior_field.draw(draw, camera, view_rect, self.get_lights(), &self.environment)
+                + self.trails.draw(draw, camera)
+        });
+
+        profiler.pass(Pass::Vehicles, || render::draw_vehicles(draw, camera, view_rect, &self.vehicles));
+        profiler.pass(Pass::Tools, || {
+            self.selection.draw(draw, camera, &self.vehicles, &self.lights)
+                + self.editor.draw(draw, camera, &self.vehicles, &self.selection)
+        });
+    }
+
+    // lays out the viewports in the window, the one under the mouse gets the camera controls
+    fn update_viewports(&mut self, app: &App) -> Vec<Rect> {
+        let viewports = self.viewports.layout.rects(app.window_rect());
+        self.viewports.resize(viewports.len(), &self.camera);
+        if self.comparison.is_active() {
+            // the copies are all shown through the main camera
+            self.viewports.active = 0;
+        } else if !app.mouse.buttons.left().is_down() && !app.mouse.buttons.right().is_down() {
+            self.viewports.active = viewports.iter()
+                .position(|viewport| viewport.contains(app.mouse.position()))
+                .unwrap_or(0);
         }
+        self.camera.offset = viewports[0].xy();
+        for (view, viewport) in self.viewports.views.iter_mut().zip(&viewports[1..]) {
+            view.camera.offset = viewport.xy();
+        }
+        viewports
+    }
 
-        for vehicle in &model.vehicles {
-            vehicle.draw(&draw, &model.camera, model.get_lights());
+    fn active_camera(&self) -> &Camera {
+        match self.viewports.active {
+            0 => &self.camera,
+            i => &self.viewports.views[i - 1].camera,
         }
+    }
 
-        draw.to_frame(app, &frame).unwrap();
-        model.egui.draw_to_frame(&frame).unwrap();
+    fn active_camera_mut(&mut self) -> &mut Camera {
+        match self.viewports.active {
+            0 => &mut self.camera,
+            i => &mut self.viewports.views[i - 1].camera,
+        }
     }
 
     fn update_scene(&mut self) {
         if self.current_scene != self.previous_scene {
-            self.load_from_file(self.current_scene);
+            self.load_from_file(self.current_scene, true);
+            self.history.clear();
             self.previous_scene = self.current_scene;
             self.follow_vehicle_indx = 0;
         }
     }
 
-    fn load_from_file(&mut self, scene: Scenes) {
+    fn load_from_file(&mut self, scene: Scenes, reset_camera: bool) {
         let scene = Scene::load_scene(scene);
         self.vehicles = scene.vehicles;
+        self.next_vehicle_id = self.vehicles.len();
         let mouse_light = Light::new(Vec2::ZERO, srgb(1.0, 1.0, 1.0), 0.7);
         self.lights = vec![mouse_light];
         self.lights.extend(scene.lights);
-        self.camera = scene.camera;
+        self.ecosystem.reset(scene.ecosystem);
+        self.environment = scene.environment;
+        self.rng = StdRng::seed_from_u64(self.seed);
+        self.trails.clear();
+        self.rewind.clear();
+        self.comparison.stop();
+        self.selection.clear();
+        if reset_camera {
+            self.camera = scene.camera;
+        }
+    }
+
+    fn update_vehicles(&mut self, delta: f32) {
+        let start = Instant::now();
+        let neighbors = self.neighbors();
+        let lights = if self.mouse_light { &self.lights } else { &self.lights[1..] };
+        let tree = self.spatial_acceleration.then(|| LightTree::new(lights, self.far_field_theta));
+        // collecting the neighbors and building the light tree count as sensing
+        let preparation = start.elapsed();
+        let (sensing, integration) = match &tree {
+            Some(tree) => step_vehicles(&mut self.vehicles, tree, &neighbors, &self.environment, delta),
+            None => step_vehicles(&mut self.vehicles, lights, &neighbors, &self.environment, delta),
+        };
+        self.profiler.record(Stage::Sensing, preparation + sensing);
+        self.profiler.record(Stage::Integration, integration);
     }
 
     fn replace_lights_on_collision(&mut self) {
-        self.lights.iter_mut().skip(self.mouse_light as usize)
-            .filter_map(|light| 
-                self.vehicles
-                    .iter()
-                    .find(|vehicle| {
-                        let distance = light.position.distance_squared(vehicle.position);
-                        distance < 20000.0 
-                    })
-                    .map(|vehicle| (light, vehicle)))
-            .for_each(|(light, vehicle)| {
-                light.position = nannou::geom::vec2(
-                    vehicle.position.x  + random_range(-1000.0, 1000.0),
-                    vehicle.position.y + random_range(-1000.0, 1000.0),
-                );
-            })
+        let start = Instant::now();
+        replace_scene_lights_on_collision(&mut self.lights, &self.vehicles, self.spatial_acceleration, &mut self.rng);
+        self.profiler.record(Stage::LightRelocation, start.elapsed());
+    }
+
+    // advances the live world, or the compared copies, by the steps of one frame
+    fn step_simulation(&mut self, update: Update, gesture_active: bool) {
+        // offline renders advance by a fixed time per frame so they do not depend on the frame rate
+        let (steps, delta) = self.capture.offline_steps()
+            .unwrap_or((self.simulation_speed, update.since_last.as_secs_f32()));
+        let frame_time = delta * steps as f32;
+        self.profiler.advance(steps, frame_time);
+        if self.comparison.is_active() {
+            comparison::step(self, steps, delta);
+            self.capture.advance(frame_time);
+            return;
+        }
+        for _ in 0..steps {
+            self.update_vehicles(delta);
+            self.replace_lights_on_collision();
+            if self.ecosystem.enabled {
+                let lights = if self.mouse_light { &self.lights } else { &self.lights[1..] };
+                self.ecosystem.step(&mut self.vehicles, lights, &mut self.next_vehicle_id, &mut self.rng, delta);
+            }
+            self.trails.record(&self.vehicles);
+            let lights = if self.mouse_light { &self.lights } else { &self.lights[1..] };
+            self.telemetry.record(&self.vehicles, lights, delta);
+        }
+
+        history::sync_selection(self, gesture_active);
+        if self.plots.enabled {
+            let vehicle = self.plotted_vehicle().map(|index| &self.vehicles[index]);
+            self.plots.record(vehicle, frame_time);
+        }
+
+        replay::record(self, frame_time);
+        rewind::record(self, frame_time);
+        self.capture.advance(frame_time);
+        self.gif_export.advance(frame_time);
+    }
+
+    fn update_cameras(&mut self, viewports: &[Rect], delta: f32) {
+        update_camera(&mut self.camera, self.camera_mode, self.follow_vehicle_indx, &self.vehicles, viewports[0], delta);
+        for (view, viewport) in self.viewports.views.iter_mut().zip(&viewports[1..]) {
+            update_camera(&mut view.camera, view.camera_mode, view.follow_vehicle_indx, &self.vehicles, *viewport, delta);
+        }
+    }
+
+    // index of the selected vehicle, or of the followed one if nothing is selected
+    fn plotted_vehicle(&self) -> Option<usize> {
+        match self.selection.selected {
+            Some(Selected::Vehicle(id)) => self.vehicles.iter().position(|vehicle| vehicle.id == id),
+            _ if self.camera_mode.follows_vehicle() && self.follow_vehicle_indx < self.vehicles.len() => Some(self.follow_vehicle_indx),
+            _ => None,
+        }
+    }
+
+    // current state of the world as a scene, the mouse light is not part of it
+    fn to_scene(&self) -> Scene {
+        Scene {
+            vehicles: self.vehicles.clone(),
+            lights: self.lights[1..].to_vec(),
+            camera: self.camera.clone(),
+            ecosystem: self.ecosystem.enabled.then(|| self.ecosystem.settings.clone()),
+            environment: self.environment.clone(),
+        }
     }
 
     fn get_lights(&self) -> &[Light] {
@@ -149,25 +455,25 @@ impl Model {
         }
     }
 
+    fn neighbors(&self) -> Vec<Neighbor> {
+        self.vehicles.iter().map(Vehicle::neighbor).collect()
+    }
+
     fn update_mouse_light(&mut self, app: &App) {
-        let mouse_pos = nannou::geom::vec2(app.mouse.x, app.mouse.y);
-        let mouse_pos_base_coords = mouse_pos / self.camera.zoom + self.camera.position;
-        self.lights[0].position = mouse_pos_base_coords;
+        self.lights[0].position = self.active_camera().to_world(app.mouse.position());
     }
 
-    fn draw_background(&self, draw: &Draw, app: &App) {
-        let grid_color_1 = srgb(0.0, 0.0, 0.0); 
-        draw.background().color(grid_color_1);
+    // returns the number of tiles drawn
+    fn draw_background(&self, draw: &Draw, camera: &Camera, window: Rect) -> usize {
         if !self.draw_background {
-            return;
+            return 0;
         }
         let grid_color_2 = srgb(0.05, 0.05, 0.05); 
-        let square_size = 500.0;
-        let zoom_adjusted_size = square_size * self.camera.zoom;
-        let window_size = app.window_rect().wh();
+        let zoom_adjusted_size = GRID_SIZE * camera.zoom;
+        let window_size = window.wh();
         let offset = Vec2::new(
-            (self.camera.position.x * self.camera.zoom).rem_euclid(zoom_adjusted_size),
-            (self.camera.position.y * self.camera.zoom).rem_euclid(zoom_adjusted_size),
+            (camera.position.x * camera.zoom).rem_euclid(zoom_adjusted_size),
+            (camera.position.y * camera.zoom).rem_euclid(zoom_adjusted_size),
         );
 
         let mut x_width_tiles = window_size.x.div_euclid(zoom_adjusted_size);
@@ -181,7 +487,8 @@ impl Model {
         
         let mut x = start_pos.x;
         let mut y = start_pos.y;
-        let mut alternator = ((self.camera.position.y * self.camera.zoom).rem_euclid(zoom_adjusted_size * 2.0) < zoom_adjusted_size) ^ ((self.camera.position.x * self.camera.zoom).rem_euclid(zoom_adjusted_size * 2.0) < zoom_adjusted_size);
+        let mut tiles = 0;
+        let mut alternator = ((camera.position.y * camera.zoom).rem_euclid(zoom_adjusted_size * 2.0) < zoom_adjusted_size) ^ ((camera.position.x * camera.zoom).rem_euclid(zoom_adjusted_size * 2.0) < zoom_adjusted_size);
         while y < end_pos.y {
             alternator = !alternator;
             let mut alternator = alternator;
@@ -192,11 +499,187 @@ impl Model {
                         .x_y(x, y)
                         .w_h(zoom_adjusted_size, zoom_adjusted_size)
                         .color(grid_color_2);
+                    tiles += 1;
                 }
                 x += zoom_adjusted_size
             }
             x = start_pos.x;
             y += zoom_adjusted_size;
         }
+        tiles
+    }
+}
+
+// moves the camera according to its mode, the viewport is the screen area the camera is shown in
+fn update_camera(camera: &mut Camera, mode: CameraMode, follow_vehicle_indx: usize, vehicles: &[Vehicle], viewport: Rect, delta: f32) {
+    match (mode, vehicles.get(follow_vehicle_indx)) {
+        (CameraMode::Follow, Some(vehicle)) => {
+            camera.position = vehicle.position;
+            camera.approach(camera.position, None, 0.0, delta);
+        }
+        (CameraMode::SmoothFollow, Some(vehicle)) => camera.approach(vehicle.position, None, 0.0, delta),
+        (CameraMode::FollowRotation, Some(vehicle)) => camera.approach(vehicle.position, None, vehicle.orientation, delta),
+        (CameraMode::FitAll, _) if !vehicles.is_empty() => {
+            let min = vehicles.iter().fold(Vec2::splat(f32::MAX), |min, vehicle| min.min(vehicle.position));
+            let max = vehicles.iter().fold(Vec2::splat(f32::MIN), |max, vehicle| max.max(vehicle.position));
+            let size = (max - min) + Vec2::splat(FIT_ALL_MARGIN);
+            let zoom = (viewport.w() / size.x).min(viewport.h() / size.y) * 0.9;
+            camera.approach((min + max) / 2.0, Some(zoom), 0.0, delta);
+        }
+        (CameraMode::GroupCentroid, _) if !vehicles.is_empty() => {
+            let sum = vehicles.iter().fold(Vec2::ZERO, |sum, vehicle| sum + vehicle.position);
+            camera.approach(sum / vehicles.len() as f32, None, 0.0, delta);
+        }
+        _ => camera.approach(camera.position, None, 0.0, delta),
+    }
+}
+
+// vehicles only read the shared lights, neighbors and environment during an update,
+// so updating them in parallel gives the same result as the serial loop.
+// all sensors are read before any vehicle drives, returns the time spent on both
+fn step_vehicles<L: LightSource + ?Sized>(vehicles: &mut [Vehicle], lights: &L, neighbors: &[Neighbor], environment: &Environment, delta: f32) -> (Duration, Duration) {
+    let start = Instant::now();
+    if vehicles.len() < PARALLEL_THRESHOLD {
+        vehicles.iter_mut().for_each(|vehicle| vehicle.sense(lights, neighbors));
+    } else {
+        vehicles.par_iter_mut().for_each(|vehicle| vehicle.sense(lights, neighbors));
+    }
+    let sensing = start.elapsed();
+
+    let start = Instant::now();
+    if vehicles.len() < PARALLEL_THRESHOLD {
+        vehicles.iter_mut().for_each(|vehicle| vehicle.drive(environment, delta));
+    } else {
+        vehicles.par_iter_mut().for_each(|vehicle| vehicle.drive(environment, delta));
+    }
+    (sensing, start.elapsed())
+}
+
+// the lights of the model start with the mouse light, it follows the cursor even while hidden and must not
+// draw from the rng, otherwise runs with the same seed would depend on the mouse
+fn replace_scene_lights_on_collision(lights: &mut [Light], vehicles: &[Vehicle], spatial_acceleration: bool, rng: &mut StdRng) {
+    replace_lights_on_collision(&mut lights[1..], vehicles, spatial_acceleration, rng);
+}
+
+// lights touched by a vehicle jump to a random position near it
+fn replace_lights_on_collision(lights: &mut [Light], vehicles: &[Vehicle], spatial_acceleration: bool, rng: &mut StdRng) {
+    let grid = spatial_acceleration
+        .then(|| SpatialGrid::new(vehicles.iter().map(|vehicle| vehicle.position), COLLISION_DISTANCE.sqrt()));
+    let collides = |light: Vec2, vehicle: Vec2| light.distance_squared(vehicle) < COLLISION_DISTANCE;
+
+    lights.iter_mut()
+        .filter_map(|light| {
+            // the grid returns the lowest index to pick the same vehicle as the linear search
+            let vehicle = match &grid {
+                Some(grid) => grid.nearby(light.position)
+                    .filter(|&i| collides(light.position, vehicles[i].position))
+                    .min()
+                    .map(|i| &vehicles[i]),
+                None => vehicles.iter().find(|vehicle| collides(light.position, vehicle.position)),
+            };
+            vehicle.map(|vehicle| (light, vehicle))
+        })
+        .for_each(|(light, vehicle)| {
+            light.position = nannou::geom::vec2(
+                vehicle.position.x  + rng.gen_range(-1000.0..1000.0),
+                vehicle.position.y + rng.gen_range(-1000.0..1000.0),
+            );
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use braitenberg_vehicle::VehicleType;
+
+    const VEHICLE_TYPES: [VehicleType; 4] = [VehicleType::TwoA, VehicleType::TwoB, VehicleType::ThreeA, VehicleType::ThreeB];
+
+    fn random_pos(rng: &mut StdRng, extent: f32) -> Vec2 {
+        Vec2::new(rng.gen_range(-extent..extent), rng.gen_range(-extent..extent))
+    }
+
+    fn random_lights(rng: &mut StdRng, count: usize, extent: f32) -> Vec<Light> {
+        (0..count)
+            .map(|_| {
+                let position = random_pos(rng, extent);
+                Light::new(position, srgb(1.0, 1.0, 1.0), rng.gen_range(0.1..1.0))
+            })
+            .collect()
+    }
+
+    fn random_vehicles(rng: &mut StdRng, count: usize, extent: f32) -> Vec<Vehicle> {
+        (0..count)
+            .map(|id| {
+                let mut vehicle = Vehicle::new(VEHICLE_TYPES[id % VEHICLE_TYPES.len()], random_pos(rng, extent));
+                vehicle.id = id;
+                vehicle.orientation = rng.gen_range(0.0..std::f32::consts::TAU);
+                vehicle
+            })
+            .collect()
+    }
+
+    #[test]
+    fn grid_collisions_match_linear_search() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // dense enough that many lights touch several vehicles at once
+        let vehicles = random_vehicles(&mut rng, 400, 2000.0);
+        let lights = random_lights(&mut rng, 1000, 2000.0);
+
+        let mut linear = lights.clone();
+        replace_lights_on_collision(&mut linear, &vehicles, false, &mut StdRng::seed_from_u64(1));
+        let mut grid = lights.clone();
+        replace_lights_on_collision(&mut grid, &vehicles, true, &mut StdRng::seed_from_u64(1));
+
+        assert!(linear != lights, "no light collided");
+        assert!(linear == grid);
+    }
+
+    #[test]
+    fn mouse_light_does_not_use_the_rng() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let vehicles = random_vehicles(&mut rng, 50, 2000.0);
+        let lights = random_lights(&mut rng, 50, 2000.0);
+
+        let results: Vec<(Vec<Light>, u64)> = [vehicles[0].position, Vec2::splat(1e6)].into_iter()
+            .map(|mouse| {
+                let mut lights = lights.clone();
+                lights.insert(0, Light::new(mouse, srgb(1.0, 1.0, 1.0), 0.7));
+                let mut rng = StdRng::seed_from_u64(1);
+                replace_scene_lights_on_collision(&mut lights, &vehicles, true, &mut rng);
+                (lights.split_off(1), rng.gen())
+            })
+            .collect();
+        assert!(results[0] == results[1]);
+    }
+
+    #[test]
+    fn light_tree_without_approximation_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lights = random_lights(&mut rng, 2000, 10_000.0);
+        let tree = LightTree::new(&lights, 0.0);
+        for _ in 0..1000 {
+            let pos = random_pos(&mut rng, 12_000.0);
+            let exact = lights[..].field_at(pos);
+            let tree = tree.field_at(pos);
+            // only the order of the summation differs
+            assert!((tree - exact).abs() <= exact * 1e-4, "{tree} != {exact} at {pos}");
+        }
+    }
+
+    #[test]
+    fn parallel_step_matches_serial_update() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let lights = random_lights(&mut rng, 50, 3000.0);
+        let mut parallel = random_vehicles(&mut rng, 2 * PARALLEL_THRESHOLD, 3000.0);
+        let mut serial = parallel.clone();
+        let environment = Environment::default();
+
+        for _ in 0..10 {
+            let neighbors: Vec<Neighbor> = serial.iter().map(Vehicle::neighbor).collect();
+            serial.iter_mut().for_each(|vehicle| vehicle.update(&lights[..], &neighbors, &environment, 0.02));
+            let neighbors: Vec<Neighbor> = parallel.iter().map(Vehicle::neighbor).collect();
+            step_vehicles(&mut parallel, &lights[..], &neighbors, &environment, 0.02);
+        }
+        assert!(serial == parallel);
     }
 }
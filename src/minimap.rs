@@ -0,0 +1,140 @@
+use nannou::{color::{srgba, Srgba}, geom::{self, Rect}, glam::{Vec2, Vec3}, App};
+
+use crate::{braitenberg_vehicle::Vehicle, camera::Camera, light::Light, Model};
+
+const MARGIN: f32 = 10.0;
+// share of the minimap left empty around the objects
+const PADDING: f32 = 0.1;
+
+// overview of the whole world in the bottom right corner of the window
+pub struct Minimap {
+    pub enabled: bool,
+    pub size: f32,
+    // the mapping is fixed while dragging, it includes the view of the camera and would move with it
+    drag: Option<Mapping>,
+    was_down: bool,
+}
+
+// maps world coordinates into the minimap, the scale is the same on both axes
+#[derive(Clone, Copy)]
+struct Mapping {
+    center: Vec2,
+    scale: f32,
+    rect: Rect,
+}
+
+impl Mapping {
+    fn to_map(&self, world_pos: Vec2) -> Vec2 {
+        Vec2::new(self.rect.x(), self.rect.y()) + (world_pos - self.center) * self.scale
+    }
+
+    fn to_world(&self, map_pos: Vec2) -> Vec2 {
+        (map_pos - Vec2::new(self.rect.x(), self.rect.y())) / self.scale + self.center
+    }
+}
+
+impl Minimap {
+    pub fn new() -> Self {
+        Minimap {
+            enabled: true,
+            size: 200.0,
+            drag: None,
+            was_down: false,
+        }
+    }
+
+    fn rect(&self, window: Rect) -> Rect {
+        Rect::from_w_h(self.size, self.size)
+            .bottom_right_of(window.pad(MARGIN))
+    }
+
+    // fits all objects and the view of the camera into the minimap
+    fn mapping(&self, camera: &Camera, window: Rect, viewport: Rect, vehicles: &[Vehicle], lights: &[Light]) -> Mapping {
+        if let Some(mapping) = self.drag {
+            return mapping;
+        }
+        let positions = vehicles.iter().map(|vehicle| vehicle.position)
+            .chain(lights.iter().map(|light| light.position))
+            .chain(view_corners(camera, viewport));
+        let (min, max) = positions.fold((Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)), |(min, max), pos| (min.min(pos), max.max(pos)));
+        let rect = self.rect(window);
+        let extent = (max - min).max_element().max(1.0);
+        Mapping {
+            center: (min + max) / 2.0,
+            scale: self.size * (1.0 - PADDING) / extent,
+            rect,
+        }
+    }
+
+    // returns the number of primitives
+    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera, window: Rect, viewport: Rect, vehicles: &[Vehicle], lights: &[Light]) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        let mapping = self.mapping(camera, window, viewport, vehicles, lights);
+        let rect = mapping.rect;
+        draw.rect()
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(srgba(0.0, 0.0, 0.0, 0.7))
+            .stroke(srgba(1.0, 1.0, 1.0, 0.5))
+            .stroke_weight(1.0);
+
+        // lights and vehicles are drawn as small squares in a single mesh
+        let mut tris = Vec::with_capacity(2 * (lights.len() + vehicles.len()));
+        for light in lights {
+            let color = srgba(light.color.red, light.color.green, light.color.blue, light.intensity.clamp(0.3, 1.0));
+            push_dot(&mut tris, mapping.to_map(light.position), 3.0, color);
+        }
+        for vehicle in vehicles {
+            let color = vehicle.vehicle_type().color();
+            push_dot(&mut tris, mapping.to_map(vehicle.position), 2.0, srgba(color.red, color.green, color.blue, 1.0));
+        }
+        draw.mesh().tris_colored(tris);
+
+        // the viewport is not axis aligned when the camera is rotated
+        let corners = view_corners(camera, viewport).map(|corner| mapping.to_map(corner));
+        draw.polyline()
+            .weight(1.0)
+            .points_closed(corners)
+            .color(srgba(1.0, 1.0, 1.0, 0.9));
+        3
+    }
+}
+
+// moves the camera to the clicked position while the left button is held on the minimap,
+// returns true if the mouse is used by the minimap
+pub fn update(app: &App, model: &mut Model, viewport: Rect, pointer_over_gui: bool) -> bool {
+    let Model { minimap, camera, vehicles, lights, .. } = model;
+    let mouse = &app.mouse;
+    let is_down = mouse.buttons.left().is_down();
+    let pressed = is_down && !minimap.was_down;
+    minimap.was_down = is_down;
+    if !minimap.enabled || !is_down {
+        minimap.drag = None;
+    }
+    if !minimap.enabled {
+        return false;
+    }
+    let mapping = minimap.mapping(camera, app.window_rect(), viewport, vehicles, &lights[1..]);
+    if pressed && !pointer_over_gui && mapping.rect.contains(mouse.position()) {
+        minimap.drag = Some(mapping);
+    }
+    if minimap.drag.is_some() {
+        camera.position = mapping.to_world(mouse.position());
+    }
+    minimap.drag.is_some() || (!pointer_over_gui && mapping.rect.contains(mouse.position()))
+}
+
+fn push_dot(tris: &mut Vec<geom::Tri<(Vec3, Srgba)>>, pos: Vec2, half: f32, color: Srgba) {
+    let corners = [Vec2::new(-half, -half), Vec2::new(half, -half), Vec2::splat(half), Vec2::new(-half, half)]
+        .map(|corner| ((pos + corner).extend(0.0), color));
+    tris.push(geom::Tri([corners[0], corners[1], corners[2]]));
+    tris.push(geom::Tri([corners[0], corners[2], corners[3]]));
+}
+
+// world positions of the corners of the viewport
+fn view_corners(camera: &Camera, viewport: Rect) -> [Vec2; 4] {
+    [viewport.bottom_left(), viewport.bottom_right(), viewport.top_right(), viewport.top_left()]
+        .map(|corner| camera.to_world(corner))
+}
@@ -0,0 +1,133 @@
+use std::{collections::VecDeque, fs::File, io::Write};
+
+use nannou_egui::egui::{self, Color32};
+
+use crate::braitenberg_vehicle::Vehicle;
+
+#[derive(Clone, Copy)]
+pub struct Sample {
+    pub time: f32,
+    pub left_sensor: f32,
+    pub right_sensor: f32,
+    pub left_motor: f32,
+    pub right_motor: f32,
+    pub speed: f32,
+    pub heading: f32,
+}
+
+type Series = (&'static str, Color32, fn(&Sample) -> f32);
+
+const LEFT_COLOR: Color32 = Color32::from_rgb(255, 200, 0);
+const RIGHT_COLOR: Color32 = Color32::from_rgb(0, 200, 255);
+
+// time series of the selected or followed vehicle, only recorded while the simulation runs
+pub struct Plots {
+    pub enabled: bool,
+    // seconds of simulated time that are shown
+    pub window: f32,
+    pub export_path: String,
+    vehicle_id: Option<usize>,
+    samples: VecDeque<Sample>,
+    time: f32,
+}
+
+impl Plots {
+    pub fn new() -> Self {
+        Plots {
+            enabled: false,
+            window: 10.0,
+            export_path: String::from("vehicle_plot.csv"),
+            vehicle_id: None,
+            samples: VecDeque::new(),
+            time: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, vehicle: Option<&Vehicle>, delta: f32) {
+        self.time += delta;
+        let id = vehicle.map(|vehicle| vehicle.id);
+        if id != self.vehicle_id {
+            self.vehicle_id = id;
+            self.samples.clear();
+        }
+        let Some(vehicle) = vehicle else {
+            return;
+        };
+        self.samples.push_back(Sample {
+            time: self.time,
+            left_sensor: vehicle.sensor_values.0,
+            right_sensor: vehicle.sensor_values.1,
+            left_motor: vehicle.motor_values.0,
+            right_motor: vehicle.motor_values.1,
+            speed: vehicle.velocity,
+            heading: vehicle.orientation.to_degrees().rem_euclid(360.0),
+        });
+        // the window can be enlarged later, keep up to a minute of history
+        while self.samples.front().is_some_and(|sample| sample.time < self.time - 60.0_f32.max(self.window)) {
+            self.samples.pop_front();
+        }
+    }
+
+    fn visible(&self) -> impl Iterator<Item = &Sample> {
+        let start = self.time - self.window;
+        self.samples.iter().filter(move |sample| sample.time >= start)
+    }
+
+    pub fn draw(&self, ui: &mut egui::Ui) {
+        let Some(id) = self.vehicle_id else {
+            ui.label("Select or follow a vehicle to plot it.");
+            return;
+        };
+        ui.label(format!("Vehicle {id}"));
+        let samples: Vec<Sample> = self.visible().copied().collect();
+        self.draw_plot(ui, "Sensors", &samples, &[
+            ("left", LEFT_COLOR, |sample| sample.left_sensor),
+            ("right", RIGHT_COLOR, |sample| sample.right_sensor),
+        ]);
+        self.draw_plot(ui, "Motors", &samples, &[
+            ("left", LEFT_COLOR, |sample| sample.left_motor),
+            ("right", RIGHT_COLOR, |sample| sample.right_motor),
+        ]);
+        self.draw_plot(ui, "Speed", &samples, &[("speed", Color32::LIGHT_GREEN, |sample| sample.speed)]);
+        self.draw_plot(ui, "Heading", &samples, &[("heading", Color32::LIGHT_RED, |sample| sample.heading)]);
+    }
+
+    fn draw_plot(&self, ui: &mut egui::Ui, title: &str, samples: &[Sample], series: &[Series]) {
+        let values = samples.iter().flat_map(|sample| series.iter().map(move |(_, _, value)| value(sample)));
+        let min = values.clone().fold(f32::MAX, f32::min).min(0.0);
+        let max = values.fold(f32::MIN, f32::max).max(min + f32::EPSILON);
+
+        let labels: Vec<String> = series.iter()
+            .map(|(name, _, value)| format!("{name}: {:.2}", samples.last().map_or(0.0, value)))
+            .collect();
+        ui.label(format!("{title} ({})", labels.join(", ")));
+
+        let (response, painter) = ui.allocate_painter(egui::vec2(300.0, 60.0), egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+        let start = self.time - self.window;
+        for (_, color, value) in series {
+            let points = samples.iter()
+                .map(|sample| egui::pos2(
+                    rect.left() + (sample.time - start) / self.window * rect.width(),
+                    rect.bottom() - (value(sample) - min) / (max - min) * rect.height(),
+                ))
+                .collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, *color)));
+        }
+    }
+
+    // writes the samples inside the plot window as csv
+    pub fn export_csv(&self) -> std::io::Result<()> {
+        let mut file = File::create(&self.export_path)?;
+        writeln!(file, "time,left_sensor,right_sensor,left_motor,right_motor,speed,heading")?;
+        for sample in self.visible() {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{}",
+                sample.time, sample.left_sensor, sample.right_sensor, sample.left_motor, sample.right_motor, sample.speed, sample.heading,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use std::{cell::RefCell, collections::VecDeque, time::{Duration, Instant}};
+
+use nannou::{color::srgba, geom::Rect, Draw};
+use nannou_egui::egui::{self, Color32};
+
+// frames kept for the graph
+const HISTORY: usize = 300;
+// the hud shows averages over this many seconds so the numbers stay readable
+const AVERAGE_SECONDS: f32 = 1.0;
+const MARGIN: f32 = 10.0;
+
+// timed parts of a simulation step
+#[derive(Clone, Copy)]
+pub enum Stage {
+    Sensing,
+    Integration,
+    LightRelocation,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 3] = [Stage::Sensing, Stage::Integration, Stage::LightRelocation];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Stage::Sensing => "Sensing",
+            Stage::Integration => "Integration",
+            Stage::LightRelocation => "Light Relocation",
+        }
+    }
+}
+
+// timed parts of drawing a frame, passes of all viewports are added up
+#[derive(Clone, Copy)]
+pub enum Pass {
+    Background,
+    Environment,
+    Lights,
+    Overlays,
+    Vehicles,
+    Tools,
+    Minimap,
+    // tessellating the recorded drawing and encoding its render commands
+    Submit,
+    Gui,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 9] = [
+        Pass::Background,
+        Pass::Environment,
+        Pass::Lights,
+        Pass::Overlays,
+        Pass::Vehicles,
+        Pass::Tools,
+        Pass::Minimap,
+        Pass::Submit,
+        Pass::Gui,
+    ];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Pass::Background => "Background",
+            Pass::Environment => "Environment",
+            Pass::Lights => "Lights",
+            Pass::Overlays => "Overlays",
+            Pass::Vehicles => "Vehicles",
+            Pass::Tools => "Selection & Editor",
+            Pass::Minimap => "Minimap",
+            Pass::Submit => "Submit",
+            Pass::Gui => "GUI",
+        }
+    }
+}
+
+// one frame, all times are in milliseconds
+#[derive(Clone, Copy, Default)]
+struct Sample {
+    // wall time since the previous frame
+    frame: f32,
+    steps: u32,
+    // time the simulation advanced
+    simulated: f32,
+    stages: [f32; Stage::ALL.len()],
+    passes: [f32; Pass::ALL.len()],
+    primitives: usize,
+}
+
+impl Sample {
+    fn update_time(&self) -> f32 {
+        self.stages.iter().sum()
+    }
+
+    fn render_time(&self) -> f32 {
+        self.passes.iter().sum()
+    }
+}
+
+// filled by the view, which only has shared access to the model
+#[derive(Default)]
+struct RenderTimes {
+    passes: [Duration; Pass::ALL.len()],
+    primitives: usize,
+}
+
+// averages over the last second
+struct Summary {
+    fps: f32,
+    steps_per_second: f32,
+    // simulated seconds per wall clock second
+    speed: f32,
+    stages: [f32; Stage::ALL.len()],
+    passes: [f32; Pass::ALL.len()],
+    primitives: usize,
+}
+
+// frame rate, simulation throughput and the time spent in the parts of the update and the view
+pub struct Profiler {
+    pub show_hud: bool,
+    pub show_graph: bool,
+    samples: VecDeque<Sample>,
+    current: Sample,
+    render: RefCell<RenderTimes>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler {
+            show_hud: false,
+            show_graph: false,
+            samples: VecDeque::new(),
+            current: Sample::default(),
+            render: RefCell::new(RenderTimes::default()),
+        }
+    }
+
+    // closes the sample of the previous update together with the view that followed it
+    pub fn begin_frame(&mut self, since_last: Duration) {
+        let render = self.render.take();
+        self.current.passes = render.passes.map(|time| time.as_secs_f32() * 1000.0);
+        self.current.primitives = render.primitives;
+        self.samples.push_back(self.current);
+        if self.samples.len() > HISTORY {
+            self.samples.pop_front();
+        }
+        self.current = Sample { frame: since_last.as_secs_f32() * 1000.0, ..Sample::default() };
+    }
+
+    pub fn record(&mut self, stage: Stage, time: Duration) {
+        self.current.stages[stage as usize] += time.as_secs_f32() * 1000.0;
+    }
+
+    // called once the simulation advanced by the given number of steps and simulated seconds
+    pub fn advance(&mut self, steps: u32, simulated: f32) {
+        self.current.steps += steps;
+        self.current.simulated += simulated * 1000.0;
+    }
+
+    // runs a render pass, the pass returns the number of primitives it drew
+    pub fn pass(&self, pass: Pass, draw: impl FnOnce() -> usize) {
+        let start = Instant::now();
+        let primitives = draw();
+        let mut render = self.render.borrow_mut();
+        render.passes[pass as usize] += start.elapsed();
+        render.primitives += primitives;
+    }
+
+    // the latest samples, the one that crosses the averaging window is still included
+    fn recent(&self) -> impl Iterator<Item = &Sample> {
+        let mut time = 0.0;
+        self.samples.iter().rev().take_while(move |sample| {
+            let start = time;
+            time += sample.frame;
+            start < AVERAGE_SECONDS * 1000.0
+        })
+    }
+
+    fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            fps: 0.0,
+            steps_per_second: 0.0,
+            speed: 0.0,
+            stages: [0.0; Stage::ALL.len()],
+            passes: [0.0; Pass::ALL.len()],
+            primitives: self.samples.back().map_or(0, |sample| sample.primitives),
+        };
+        let (mut wall, mut count) = (0.0, 0);
+        for sample in self.recent() {
+            wall += sample.frame;
+            count += 1;
+            summary.steps_per_second += sample.steps as f32;
+            summary.speed += sample.simulated;
+            summary.stages.iter_mut().zip(sample.stages).for_each(|(sum, time)| *sum += time);
+            summary.passes.iter_mut().zip(sample.passes).for_each(|(sum, time)| *sum += time);
+        }
+        if wall <= 0.0 {
+            return summary;
+        }
+        summary.speed /= wall;
+        let seconds = wall / 1000.0;
+        summary.fps = count as f32 / seconds;
+        summary.steps_per_second /= seconds;
+        summary.stages.iter_mut().chain(summary.passes.iter_mut()).for_each(|time| *time /= count as f32);
+        summary
+    }
+
+    // overlay in the top right corner of the window
+    pub fn draw_hud(&self, draw: &Draw, window: Rect, requested_speed: u32, vehicles: usize, lights: usize) {
+        if !self.show_hud {
+            return;
+        }
+        let summary = self.summary();
+        let mut lines = vec![
+            format!("FPS: {:.1} ({:.1} ms)", summary.fps, 1000.0 / summary.fps.max(f32::EPSILON)),
+            format!("Steps/s: {:.0}", summary.steps_per_second),
+            format!("Speed: {:.2}x (requested {requested_speed}x)", summary.speed),
+        ];
+        lines.extend(Stage::ALL.iter().map(|stage| format!("{}: {:.2} ms", stage.to_str(), summary.stages[*stage as usize])));
+        lines.extend(Pass::ALL.iter().map(|pass| format!("{}: {:.2} ms", pass.to_str(), summary.passes[*pass as usize])));
+        lines.push(format!("Vehicles: {vehicles}, Lights: {lights}"));
+        lines.push(format!("Primitives: {}", summary.primitives));
+
+        let rect = Rect::from_w_h(260.0, lines.len() as f32 * 16.0 + 2.0 * MARGIN)
+            .top_right_of(window.pad(MARGIN));
+        draw.rect()
+            .xy(rect.xy())
+            .wh(rect.wh())
+            .color(srgba(0.0, 0.0, 0.0, 0.7));
+        draw.text(&lines.join("\n"))
+            .xy(rect.xy())
+            .wh(rect.pad(MARGIN).wh())
+            .font_size(12)
+            .left_justify()
+            .align_text_top()
+            .color(srgba(1.0, 1.0, 1.0, 0.9));
+    }
+
+    // rolling graph of the frame time split into simulation and rendering
+    pub fn draw_graph(&self, ui: &mut egui::Ui) {
+        let series: [(&str, Color32, fn(&Sample) -> f32); 3] = [
+            ("frame", Color32::WHITE, |sample| sample.frame),
+            ("update", Color32::LIGHT_GREEN, Sample::update_time),
+            ("render", Color32::LIGHT_BLUE, Sample::render_time),
+        ];
+        let latest = self.samples.back().copied().unwrap_or_default();
+        let labels: Vec<String> = series.iter()
+            .map(|(name, _, value)| format!("{name}: {:.1} ms", value(&latest)))
+            .collect();
+        ui.label(labels.join(", "));
+
+        let (response, painter) = ui.allocate_painter(egui::vec2(300.0, 100.0), egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 0.0, Color32::from_gray(20));
+        // the scale only shrinks to 60 fps so a smooth run shows as a flat line at the bottom
+        let max = self.samples.iter()
+            .flat_map(|sample| series.iter().map(move |(_, _, value)| value(sample)))
+            .fold(1000.0 / 60.0, f32::max);
+        let target = rect.bottom() - (1000.0 / 60.0) / max * rect.height();
+        painter.line_segment([egui::pos2(rect.left(), target), egui::pos2(rect.right(), target)], egui::Stroke::new(1.0, Color32::from_gray(80)));
+        for (_, color, value) in series {
+            let points = self.samples.iter().enumerate()
+                .map(|(i, sample)| egui::pos2(
+                    rect.left() + i as f32 / HISTORY as f32 * rect.width(),
+                    rect.bottom() - value(sample) / max * rect.height(),
+                ))
+                .collect();
+            painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, color)));
+        }
+        ui.label(format!("The grey line marks 60 fps, the graph covers the last {HISTORY} frames."));
+    }
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::TAU;
+
+use nannou::{color::{srgba, Srgba}, geom::{self, Rect}, glam::{Vec2, Vec3}, wgpu::{BlendComponent, BlendFactor, BlendOperation}};
+
+use crate::{
+    braitenberg_vehicle::{sensor_field, sensor_response, Vehicle},
+    camera::Camera,
+    light::Light,
+    spatial::Falloff,
+};
+
+// vehicles shorter than this on screen are drawn as a single triangle without wheels and wires
+const DETAIL_LENGTH: f32 = 30.0;
+// glyphs keep this length on screen so that far away vehicles do not vanish
+const MIN_GLYPH_LENGTH: f32 = 6.0;
+// screen length of one segment of the light circles, limited by the segment counts below
+const LIGHT_SEGMENT_LENGTH: f32 = 20.0;
+const MIN_LIGHT_SEGMENTS: usize = 8;
+const MAX_LIGHT_SEGMENTS: usize = 32;
+// screen width of one ring of the glow between the saturated core and its outer edge, limited by the ring counts below,
+// a light never takes more than MAX_LIGHT_SEGMENTS * (2 * MAX_GLOW_RINGS + 1) triangles
+const GLOW_RING_WIDTH: f32 = 40.0;
+const MIN_GLOW_RINGS: usize = 2;
+const MAX_GLOW_RINGS: usize = 8;
+// sensor value at which the glow ends
+const MIN_GLOW: f32 = 0.005;
+
+// adds the color weighted by its alpha to what is already drawn, the blending happens in linear color
+// so overlapping lights add up like their fields do
+const ADDITIVE: BlendComponent = BlendComponent {
+    src_factor: BlendFactor::SrcAlpha,
+    dst_factor: BlendFactor::One,
+    operation: BlendOperation::Add,
+};
+
+type ColoredTri = geom::Tri<(Vec3, Srgba)>;
+
+// true if a circle around the world position overlaps the view rect, which is given in drawing coordinates
+pub fn is_visible(camera: &Camera, view_rect: Rect, position: Vec2, radius: f32) -> bool {
+    let pos = camera.to_screen(position);
+    let radius = radius * camera.zoom;
+    pos.x + radius >= view_rect.left() && pos.x - radius <= view_rect.right()
+        && pos.y + radius >= view_rect.bottom() && pos.y - radius <= view_rect.top()
+}
+
+// draws the glow of all visible lights as one mesh, the brightness at every point is the sensor value
+// a single light produces there with the given falloff, returns the number of primitives
+pub fn draw_lights(draw: &nannou::draw::Draw, camera: &Camera, view_rect: Rect, falloff: &Falloff) -> usize {
+    let mut tris = Vec::new();
+    for light in falloff.lights {
+        let radius = falloff.distance_for(light, sensor_field(MIN_GLOW));
+        if light.intensity > 0.0 && radius.is_finite() && is_visible(camera, view_rect, light.position, radius) {
+            push_glow(&mut tris, light, falloff, camera, radius);
+        }
+    }
+    if tris.is_empty() {
+        return 0;
+    }
+    draw.color_blend(ADDITIVE).mesh().tris_colored(tris);
+    1
+}
+
+fn push_glow(tris: &mut Vec<ColoredTri>, light: &Light, falloff: &Falloff, camera: &Camera, radius: f32) {
+    let screen_radius = radius * camera.zoom;
+    let segments = ((screen_radius / LIGHT_SEGMENT_LENGTH) as usize).clamp(MIN_LIGHT_SEGMENTS, MAX_LIGHT_SEGMENTS);
+    let rings = ((screen_radius / GLOW_RING_WIDTH) as usize).clamp(MIN_GLOW_RINGS, MAX_GLOW_RINGS);
+    let directions: Vec<Vec2> = (0..=segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * TAU;
+            Vec2::new(angle.cos(), angle.sin())
+        })
+        .collect();
+    let center = camera.to_screen(light.position);
+    let color = |distance: f32| {
+        let brightness = sensor_response(falloff.light_field(light, distance));
+        srgba(light.color.red, light.color.green, light.color.blue, brightness)
+    };
+    let vertex = |direction: Vec2, distance: f32, color: Srgba| ((center + direction * distance * camera.zoom).extend(0.0), color);
+
+    // the sensors are saturated inside the core
+    let core = falloff.distance_for(light, sensor_field(1.0));
+    let core_color = color(core);
+    for pair in directions.windows(2) {
+        tris.push(geom::Tri([(center.extend(0.0), core_color), vertex(pair[0], core, core_color), vertex(pair[1], core, core_color)]));
+    }
+
+    // the rings get wider towards the outside where the field changes slowly
+    let radii: Vec<f32> = (0..=rings)
+        .map(|i| core * (radius / core).powf(i as f32 / rings as f32))
+        .collect();
+    for ring in radii.windows(2) {
+        let (inner, outer) = (color(ring[0]), color(ring[1]));
+        for pair in directions.windows(2) {
+            let corners = [
+                vertex(pair[0], ring[0], inner),
+                vertex(pair[1], ring[0], inner),
+                vertex(pair[1], ring[1], outer),
+                vertex(pair[0], ring[1], outer),
+            ];
+            tris.push(geom::Tri([corners[0], corners[1], corners[2]]));
+            tris.push(geom::Tri([corners[0], corners[2], corners[3]]));
+        }
+    }
+}
+
+// draws the bodies of all visible vehicles as one mesh, wheels and wires are only drawn for vehicles large enough to show them,
+// returns the number of primitives
+pub fn draw_vehicles(draw: &nannou::draw::Draw, camera: &Camera, view_rect: Rect, vehicles: &[Vehicle]) -> usize {
+    let visible: Vec<&Vehicle> = vehicles.iter()
+        .filter(|vehicle| is_visible(camera, view_rect, vehicle.position, vehicle.length.max(vehicle.width)))
+        .collect();
+    if visible.is_empty() {
+        return 0;
+    }
+
+    let mut tris = Vec::with_capacity(visible.len() * 2);
+    for vehicle in &visible {
+        if is_detailed(vehicle, camera) {
+            push_body(&mut tris, vehicle, camera);
+        } else {
+            push_glyph(&mut tris, vehicle, camera);
+        }
+    }
+    draw.mesh().tris_colored(tris);
+
+    let details: usize = visible.into_iter()
+        .filter(|vehicle| is_detailed(vehicle, camera))
+        .map(|vehicle| vehicle.draw_details(draw, camera))
+        .sum();
+    1 + details
+}
+
+fn is_detailed(vehicle: &Vehicle, camera: &Camera) -> bool {
+    vehicle.length * camera.zoom >= DETAIL_LENGTH
+}
+
+fn vertex(vehicle: &Vehicle, camera: &Camera, offset: Vec2) -> (Vec3, Srgba) {
+    let color = vehicle.vehicle_type().color();
+    (vehicle.to_global_cords(offset, camera).extend(0.0), srgba(color.red, color.green, color.blue, 1.0))
+}
+
+// the rectangular body as two triangles
+fn push_body(tris: &mut Vec<ColoredTri>, vehicle: &Vehicle, camera: &Camera) {
+    let half = Vec2::new(vehicle.width, vehicle.length) / 2.0;
+    let corners = [Vec2::new(-half.x, -half.y), Vec2::new(half.x, -half.y), half, Vec2::new(-half.x, half.y)]
+        .map(|corner| vertex(vehicle, camera, corner));
+    tris.push(geom::Tri([corners[0], corners[1], corners[2]]));
+    tris.push(geom::Tri([corners[0], corners[2], corners[3]]));
+}
+
+// a triangle pointing in the driving direction
+fn push_glyph(tris: &mut Vec<ColoredTri>, vehicle: &Vehicle, camera: &Camera) {
+    let scale = (MIN_GLYPH_LENGTH / (vehicle.length * camera.zoom)).max(1.0);
+    let half = Vec2::new(vehicle.width, vehicle.length) / 2.0 * scale;
+    tris.push(geom::Tri([
+        vertex(vehicle, camera, Vec2::new(0.0, half.y)),
+        vertex(vehicle, camera, Vec2::new(-half.x, -half.y)),
+        vertex(vehicle, camera, Vec2::new(half.x, -half.y)),
+    ]));
+}
@@ -0,0 +1,198 @@
+use std::{fs::File, io::{BufReader, BufWriter}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{braitenberg_vehicle::Vehicle, light::Light, snapshot::Snapshot, Model};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReplayFrame {
+    pub time: f32,
+    pub vehicles: Vec<Vehicle>,
+    // sensor and motor values of the vehicles, they are not serialized as part of a vehicle
+    pub readings: Vec<((f32, f32), (f32, f32))>,
+    // lights that were active, including the mouse light
+    pub lights: Vec<Light>,
+}
+
+impl ReplayFrame {
+    fn capture(model: &Model, time: f32) -> Self {
+        ReplayFrame {
+            time,
+            vehicles: model.vehicles.clone(),
+            readings: model.vehicles.iter().map(|vehicle| (vehicle.sensor_values, vehicle.motor_values)).collect(),
+            lights: model.get_lights().to_vec(),
+        }
+    }
+}
+
+// a recorded run: the scene it started from, the seed of the simulation and the state after every frame
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub initial: Snapshot,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn duration(&self) -> f32 {
+        self.frames.last().map_or(0.0, |frame| frame.time)
+    }
+
+    // index of the last frame at or before the given time
+    fn frame_at(&self, time: f32) -> usize {
+        self.frames.partition_point(|frame| frame.time <= time).saturating_sub(1)
+    }
+}
+
+pub struct Playback {
+    replay: Replay,
+    pub time: f32,
+    pub playing: bool,
+    pub speed: f32,
+    // state of the live simulation, restored when the playback ends
+    live: Snapshot,
+    live_mouse_light: bool,
+}
+
+impl Playback {
+    pub fn duration(&self) -> f32 {
+        self.replay.duration()
+    }
+
+    pub fn frame(&self) -> usize {
+        self.replay.frame_at(self.time)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.replay.frames.len()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    // moves the given number of frames forward or back and pauses
+    pub fn step(&mut self, frames: isize) {
+        let frame = self.frame().saturating_add_signed(frames).min(self.frame_count().saturating_sub(1));
+        if let Some(frame) = self.replay.frames.get(frame) {
+            self.time = frame.time;
+        }
+        self.playing = false;
+    }
+}
+
+pub struct Replays {
+    pub path: String,
+    recording: Option<Replay>,
+    time: f32,
+    pub playback: Option<Playback>,
+}
+
+impl Replays {
+    pub fn new() -> Self {
+        Replays {
+            path: String::from("replay.json"),
+            recording: None,
+            time: 0.0,
+            playback: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    // number of recorded frames
+    pub fn frames(&self) -> usize {
+        self.recording.as_ref().map_or(0, |replay| replay.frames.len())
+    }
+}
+
+// records from the current state on, the rng is reseeded so the run can be reproduced from the file
+pub fn start_recording(model: &mut Model) {
+    model.rng = rand::SeedableRng::seed_from_u64(model.seed);
+    let mut replay = Replay {
+        seed: model.seed,
+        initial: Snapshot::capture(model),
+        frames: Vec::new(),
+    };
+    replay.frames.push(ReplayFrame::capture(model, 0.0));
+    model.replays.recording = Some(replay);
+    model.replays.time = 0.0;
+}
+
+// adds the state after a frame of the simulation, delta is the simulated time of the frame
+pub fn record(model: &mut Model, delta: f32) {
+    if model.replays.recording.is_none() {
+        return;
+    }
+    model.replays.time += delta;
+    let frame = ReplayFrame::capture(model, model.replays.time);
+    if let Some(replay) = &mut model.replays.recording {
+        replay.frames.push(frame);
+    }
+}
+
+pub fn stop_recording(model: &mut Model) -> std::io::Result<()> {
+    let Some(replay) = model.replays.recording.take() else {
+        return Ok(());
+    };
+    let file = BufWriter::new(File::create(&model.replays.path)?);
+    serde_json::to_writer(file, &replay)?;
+    Ok(())
+}
+
+// loads the replay file and shows its first frame, the live simulation is kept until the playback ends
+pub fn start_playback(model: &mut Model) -> std::io::Result<()> {
+    let file = BufReader::new(File::open(&model.replays.path)?);
+    let replay: Replay = serde_json::from_reader(file)?;
+    if replay.frames.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "replay has no frames"));
+    }
+    let live = Snapshot::capture(model);
+    let live_mouse_light = model.mouse_light;
+    replay.initial.restore(model);
+    model.replays.playback = Some(Playback {
+        replay,
+        time: 0.0,
+        playing: false,
+        speed: 1.0,
+        live,
+        live_mouse_light,
+    });
+    update_playback(model, 0.0);
+    Ok(())
+}
+
+pub fn stop_playback(model: &mut Model) {
+    if let Some(playback) = model.replays.playback.take() {
+        playback.live.restore(model);
+        model.mouse_light = playback.live_mouse_light;
+    }
+}
+
+// advances the playback by the given real time and shows the frame at the playback time
+pub fn update_playback(model: &mut Model, delta: f32) {
+    let Some(playback) = &mut model.replays.playback else {
+        return;
+    };
+    if playback.playing {
+        playback.time += delta * playback.speed;
+        if playback.time >= playback.duration() {
+            playback.time = playback.duration();
+            playback.playing = false;
+        }
+    }
+    playback.time = playback.time.clamp(0.0, playback.duration());
+    let frame = &playback.replay.frames[playback.frame()];
+
+    model.vehicles = frame.vehicles.clone();
+    for (vehicle, (sensors, motors)) in model.vehicles.iter_mut().zip(&frame.readings) {
+        vehicle.sensor_values = *sensors;
+        vehicle.motor_values = *motors;
+    }
+    // the recorded mouse light is shown as a normal light
+    model.mouse_light = false;
+    model.lights.truncate(1);
+    model.lights.extend(frame.lights.iter().cloned());
+}
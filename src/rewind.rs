@@ -0,0 +1,88 @@
+use std::collections::VecDeque;
+
+use crate::{history::Command, snapshot::Snapshot, Model};
+
+// ring buffer of snapshots of the live simulation to jump back in time
+pub struct Rewind {
+    pub enabled: bool,
+    // seconds of simulated time that are kept
+    pub duration: f32,
+    // seconds of simulated time between two snapshots
+    pub interval: f32,
+    // how far a rewind goes back
+    pub seconds: f32,
+    snapshots: VecDeque<(f32, Snapshot)>,
+    time: f32,
+    next_snapshot: f32,
+}
+
+impl Rewind {
+    pub fn new() -> Self {
+        Rewind {
+            // every snapshot clones the whole world, so large scenes only pay for it when asked to
+            enabled: false,
+            duration: 30.0,
+            interval: 0.25,
+            seconds: 5.0,
+            snapshots: VecDeque::new(),
+            time: 0.0,
+            next_snapshot: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.time = 0.0;
+        self.next_snapshot = 0.0;
+    }
+
+    // seconds of simulated time that can be rewound
+    pub fn available(&self) -> f32 {
+        self.snapshots.front().map_or(0.0, |(time, _)| self.time - time)
+    }
+}
+
+// takes a snapshot whenever the interval has passed, delta is the simulated time of the frame
+pub fn record(model: &mut Model, delta: f32) {
+    if !model.rewind.enabled {
+        return;
+    }
+    model.rewind.time += delta;
+    if model.rewind.time < model.rewind.next_snapshot {
+        return;
+    }
+    let snapshot = Snapshot::capture(model);
+    let rewind = &mut model.rewind;
+    rewind.snapshots.push_back((rewind.time, snapshot));
+    rewind.next_snapshot = rewind.time + rewind.interval;
+    while rewind.snapshots.front().is_some_and(|(time, _)| *time < rewind.time - rewind.duration) {
+        rewind.snapshots.pop_front();
+    }
+}
+
+// restores the newest snapshot that is at least the given seconds old and returns how far it went back,
+// the snapshots after it are dropped so the simulation can continue on a different path
+pub fn restore(model: &mut Model, seconds: f32) -> Option<f32> {
+    let rewind = &mut model.rewind;
+    let target = rewind.time - seconds;
+    let index = rewind.snapshots.iter().rposition(|(time, _)| *time <= target).unwrap_or(0);
+    rewind.snapshots.truncate(index + 1);
+    let (time, snapshot) = rewind.snapshots.back().cloned()?;
+    let seconds = rewind.time - time;
+    rewind.time = time;
+    rewind.next_snapshot = time + rewind.interval;
+    snapshot.restore(model);
+    Some(seconds)
+}
+
+// rewinds by the configured seconds as an undoable step and pauses to allow changes before resuming
+pub fn rewind(model: &mut Model) {
+    let before = Snapshot::capture(model);
+    let seconds = model.rewind.seconds;
+    let Some(seconds) = restore(model, seconds) else {
+        return;
+    };
+    let after = Snapshot::capture(model);
+    model.history.push(Command::Rewind { seconds, before: Box::new(before), after: Box::new(after) });
+    model.paused = true;
+}
@@ -1,7 +1,7 @@
 use std::fs::File;
 use serde::{Deserialize, Serialize};
 
-use crate::{braitenberg_vehicle::Vehicle, camera::Camera, light::Light};
+use crate::{braitenberg_vehicle::Vehicle, camera::Camera, ecosystem::EcosystemSettings, environment::Environment, light::Light};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum Scenes {
@@ -12,6 +12,9 @@ pub enum Scenes {
     Scene5,
     Scene6,
     Scene7,
+    Scene8,
+    Scene9,
+    Scene10,
 }
 
 impl Scenes {
@@ -24,6 +27,24 @@ impl Scenes {
             Scenes::Scene5 => "Scene 5",
             Scenes::Scene6 => "Scene 6",
             Scenes::Scene7 => "Scene 7",
+            Scenes::Scene8 => "Scene 8",
+            Scenes::Scene9 => "Scene 9",
+            Scenes::Scene10 => "Scene 10",
+        }
+    }
+
+    pub fn file_path(&self) -> &str {
+        match self {
+            Scenes::Scene1 => "scenes/scene1.json",
+            Scenes::Scene2 => "scenes/scene2.json",
+            Scenes::Scene3 => "scenes/scene3.json",
+            Scenes::Scene4 => "scenes/scene4.json",
+            Scenes::Scene5 => "scenes/scene5.json",
+            Scenes::Scene6 => "scenes/scene6.json",
+            Scenes::Scene7 => "scenes/scene7.json",
+            Scenes::Scene8 => "scenes/scene8.json",
+            Scenes::Scene9 => "scenes/scene9.json",
+            Scenes::Scene10 => "scenes/scene10.json",
         }
     }
 }
@@ -33,22 +54,25 @@ pub struct Scene {
     pub vehicles: Vec<Vehicle>,
     pub lights: Vec<Light>,
     pub camera: Camera,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecosystem: Option<EcosystemSettings>,
+    #[serde(default, skip_serializing_if = "Environment::is_empty")]
+    pub environment: Environment,
 }
 
 impl Scene {
     pub fn load_scene(scene:Scenes) -> Self {
-        let file_path = match scene {
-            Scenes::Scene1 => "scenes/scene1.json",
-            Scenes::Scene2 => "scenes/scene2.json",
-            Scenes::Scene3 => "scenes/scene3.json",
-            Scenes::Scene4 => "scenes/scene4.json",
-            Scenes::Scene5 => "scenes/scene5.json",
-            Scenes::Scene6 => "scenes/scene6.json",
-            Scenes::Scene7 => "scenes/scene7.json",
-        };
+        let file = File::open(scene.file_path()).unwrap();
+        let mut scene: Scene = serde_json::from_reader(file).unwrap();
+        for (id, vehicle) in scene.vehicles.iter_mut().enumerate() {
+            vehicle.id = id;
+        }
+        scene
+    }
 
-        let file = File::open(file_path).unwrap();
-        serde_json::from_reader(file).unwrap()
+    pub fn save_scene(&self, path: &str) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
     }
 }
-
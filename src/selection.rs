@@ -0,0 +1,119 @@
+use nannou::{color::srgba, glam::Vec2, math::Vec2Rotate, state::Mouse};
+
+use crate::{braitenberg_vehicle::Vehicle, camera::Camera, light::Light};
+
+// objects smaller than this on screen are still easy to click
+const MIN_PICK_RADIUS: f32 = 15.0;
+const LIGHT_PICK_RADIUS: f32 = 100.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Selected {
+    // id of the vehicle
+    Vehicle(usize),
+    // index into the lights of the model, the mouse light at index 0 can't be selected
+    Light(usize),
+}
+
+pub struct Selection {
+    pub selected: Option<Selected>,
+    // offset from the mouse to the dragged object in world coordinates
+    drag_offset: Option<Vec2>,
+    was_down: bool,
+}
+
+impl Selection {
+    pub fn new() -> Self {
+        Selection {
+            selected: None,
+            drag_offset: None,
+            was_down: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.selected = None;
+        self.drag_offset = None;
+    }
+
+    // selects the object under the mouse on left click and moves it while the button is held
+    pub fn update(&mut self, mouse: &Mouse, camera: &Camera, vehicles: &mut [Vehicle], lights: &mut [Light], pointer_over_gui: bool) {
+        let is_down = mouse.buttons.left().is_down();
+        let pressed = is_down && !self.was_down;
+        self.was_down = is_down;
+        let mouse_pos = camera.to_world(mouse.position());
+
+        if pressed && !pointer_over_gui {
+            self.selected = pick(mouse_pos, camera, vehicles, lights);
+            self.drag_offset = self.position(vehicles, lights).map(|pos| pos - mouse_pos);
+        }
+        if !is_down {
+            self.drag_offset = None;
+        }
+
+        match (self.selected, self.drag_offset) {
+            (Some(Selected::Vehicle(id)), Some(offset)) => {
+                if let Some(vehicle) = vehicles.iter_mut().find(|vehicle| vehicle.id == id) {
+                    vehicle.position = mouse_pos + offset;
+                }
+            }
+            (Some(Selected::Light(index)), Some(offset)) => {
+                if let Some(light) = lights.get_mut(index) {
+                    light.position = mouse_pos + offset;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.drag_offset.is_some()
+    }
+
+    // world position of the selected object, None if it no longer exists
+    pub fn position(&self, vehicles: &[Vehicle], lights: &[Light]) -> Option<Vec2> {
+        match self.selected? {
+            Selected::Vehicle(id) => vehicles.iter().find(|vehicle| vehicle.id == id).map(|vehicle| vehicle.position),
+            Selected::Light(index) => lights.get(index).map(|light| light.position),
+        }
+    }
+
+    // returns the number of primitives
+    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera, vehicles: &[Vehicle], lights: &[Light]) -> usize {
+        let Some(position) = self.position(vehicles, lights) else {
+            return 0;
+        };
+        let radius = match self.selected {
+            Some(Selected::Vehicle(id)) => vehicles.iter()
+                .find(|vehicle| vehicle.id == id)
+                .map_or(0.0, |vehicle| vehicle.width.max(vehicle.length) * 0.75),
+            _ => LIGHT_PICK_RADIUS,
+        };
+        let pos = camera.to_screen(position);
+        draw.ellipse()
+            .x_y(pos.x, pos.y)
+            .radius((radius * camera.zoom).max(MIN_PICK_RADIUS))
+            .no_fill()
+            .stroke(srgba(1.0, 1.0, 1.0, 0.8))
+            .stroke_weight(2.0);
+        1
+    }
+}
+
+// returns the topmost object at the given world position, vehicles are drawn above lights
+fn pick(pos: Vec2, camera: &Camera, vehicles: &[Vehicle], lights: &[Light]) -> Option<Selected> {
+    let min_radius = MIN_PICK_RADIUS / camera.zoom;
+    let vehicle = vehicles.iter().rev().find(|vehicle| {
+        let local = (pos - vehicle.position).rotate(-vehicle.orientation);
+        (local.x.abs() <= vehicle.width / 2.0 && local.y.abs() <= vehicle.length / 2.0)
+            || local.length() <= min_radius
+    });
+    if let Some(vehicle) = vehicle {
+        return Some(Selected::Vehicle(vehicle.id));
+    }
+    lights.iter()
+        .enumerate()
+        .skip(1)
+        .rev()
+        .find(|(_, light)| light.position.distance(pos) <= LIGHT_PICK_RADIUS.max(min_radius))
+        .map(|(index, _)| Selected::Light(index))
+}
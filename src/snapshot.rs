@@ -0,0 +1,47 @@
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+
+use crate::{braitenberg_vehicle::Vehicle, ecosystem::EcosystemState, environment::Environment, light::Light, Model};
+
+// state of the simulated world, everything needed to continue the simulation from this point
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub vehicles: Vec<Vehicle>,
+    // lights of the scene without the mouse light
+    pub lights: Vec<Light>,
+    pub environment: Environment,
+    pub next_vehicle_id: usize,
+    // not saved in replay files, their playback does not simulate
+    #[serde(skip)]
+    pub rng: Option<StdRng>,
+    #[serde(skip)]
+    pub ecosystem: Option<EcosystemState>,
+}
+
+impl Snapshot {
+    pub fn capture(model: &Model) -> Self {
+        Snapshot {
+            vehicles: model.vehicles.clone(),
+            lights: model.lights[1..].to_vec(),
+            environment: model.environment.clone(),
+            next_vehicle_id: model.next_vehicle_id,
+            rng: Some(model.rng.clone()),
+            ecosystem: Some(model.ecosystem.state()),
+        }
+    }
+
+    // replaces the world of the model, the mouse light is kept
+    pub fn restore(&self, model: &mut Model) {
+        model.vehicles = self.vehicles.clone();
+        model.lights.truncate(1);
+        model.lights.extend(self.lights.iter().cloned());
+        model.environment = self.environment.clone();
+        model.next_vehicle_id = self.next_vehicle_id;
+        if let Some(rng) = &self.rng {
+            model.rng = rng.clone();
+        }
+        if let Some(ecosystem) = self.ecosystem {
+            model.ecosystem.restore(ecosystem);
+        }
+    }
+}
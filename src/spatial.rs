@@ -0,0 +1,266 @@
+use std::{collections::HashMap, time::Instant};
+
+use nannou::{glam::Vec2, rand::random_range};
+
+use crate::light::Light;
+
+// lights per quadtree leaf before it is split
+const LEAF_SIZE: usize = 8;
+// stops splitting when many lights share the same position
+const MAX_DEPTH: usize = 24;
+
+// anything that can tell the summed light intensity (intensity / distance^2) at a position
+pub trait LightSource: Sync {
+    fn field_at(&self, pos: Vec2) -> f32;
+}
+
+// brute force sum over all lights
+impl LightSource for [Light] {
+    fn field_at(&self, pos: Vec2) -> f32 {
+        self.iter()
+            .map(|light| {
+                let dist = light.position.distance_squared(pos);
+                light.intensity / dist
+            })
+            .sum()
+    }
+}
+
+// brute force sum with an adjustable falloff (intensity / distance^exponent) scaled by a gain,
+// an exponent of 2.0 and a gain of 1.0 give the same field as the plain lights
+pub struct Falloff<'a> {
+    pub lights: &'a [Light],
+    pub gain: f32,
+    pub exponent: f32,
+}
+
+impl<'a> Falloff<'a> {
+    // the falloff of the plain lights
+    pub fn new(lights: &'a [Light]) -> Self {
+        Falloff { lights, gain: 1.0, exponent: 2.0 }
+    }
+
+    // field of a single light at the given distance
+    pub fn light_field(&self, light: &Light, distance: f32) -> f32 {
+        self.gain * light.intensity / distance.powf(self.exponent)
+    }
+
+    // distance at which the field of a single light has fallen to the given value
+    pub fn distance_for(&self, light: &Light, field: f32) -> f32 {
+        (self.gain * light.intensity / field).powf(1.0 / self.exponent)
+    }
+}
+
+impl LightSource for Falloff<'_> {
+    fn field_at(&self, pos: Vec2) -> f32 {
+        let half_exponent = self.exponent / 2.0;
+        self.lights.iter()
+            .map(|light| light.intensity / light.position.distance_squared(pos).powf(half_exponent))
+            .sum::<f32>() * self.gain
+    }
+}
+
+struct Node {
+    min: Vec2,
+    size: f32,
+    // intensity weighted center of all lights in the node
+    center: Vec2,
+    intensity: f32,
+    // index of the first of four children, zero for leaves
+    children: usize,
+    lights: Vec<(Vec2, f32)>,
+}
+
+impl Node {
+    fn new(min: Vec2, size: f32) -> Self {
+        Node {
+            min,
+            size,
+            center: min + Vec2::splat(size / 2.0),
+            intensity: 0.0,
+            children: 0,
+            lights: Vec::new(),
+        }
+    }
+}
+
+// quadtree over the lights, distant nodes are approximated by a single light at their center
+// in the style of Barnes-Hut. theta is the largest node size to distance ratio that gets approximated,
+// a theta of 0.0 visits every light and gives the exact result.
+pub struct LightTree {
+    nodes: Vec<Node>,
+    pub theta: f32,
+}
+
+impl LightTree {
+    pub fn new(lights: &[Light], theta: f32) -> Self {
+        let mut tree = LightTree { nodes: Vec::new(), theta };
+        if lights.is_empty() {
+            return tree;
+        }
+        let min = lights.iter().fold(Vec2::splat(f32::MAX), |min, light| min.min(light.position));
+        let max = lights.iter().fold(Vec2::splat(f32::MIN), |max, light| max.max(light.position));
+        let size = (max - min).max_element().max(1.0);
+        let points = lights.iter().map(|light| (light.position, light.intensity)).collect();
+        tree.nodes.push(Node::new(min, size));
+        tree.build(0, points, 0);
+        tree
+    }
+
+    fn build(&mut self, index: usize, lights: Vec<(Vec2, f32)>, depth: usize) {
+        let (min, size) = (self.nodes[index].min, self.nodes[index].size);
+        let intensity: f32 = lights.iter().map(|(_, intensity)| intensity).sum();
+        if intensity > 0.0 {
+            self.nodes[index].center = lights.iter().fold(Vec2::ZERO, |sum, (pos, intensity)| sum + *pos * *intensity) / intensity;
+        }
+        self.nodes[index].intensity = intensity;
+
+        if lights.len() <= LEAF_SIZE || depth >= MAX_DEPTH {
+            self.nodes[index].lights = lights;
+            return;
+        }
+
+        let half = size / 2.0;
+        let mut quadrants: [Vec<(Vec2, f32)>; 4] = Default::default();
+        for light in lights {
+            let quadrant = (light.0.x >= min.x + half) as usize + 2 * (light.0.y >= min.y + half) as usize;
+            quadrants[quadrant].push(light);
+        }
+
+        // children are stored next to each other so only the first index is needed
+        let first_child = self.nodes.len();
+        for i in 0..4 {
+            let child_min = min + Vec2::new((i % 2) as f32, (i / 2) as f32) * half;
+            self.nodes.push(Node::new(child_min, half));
+        }
+        self.nodes[index].children = first_child;
+        for (i, quadrant) in quadrants.into_iter().enumerate() {
+            self.build(first_child + i, quadrant, depth + 1);
+        }
+    }
+}
+
+impl LightSource for LightTree {
+    fn field_at(&self, pos: Vec2) -> f32 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        let theta_squared = self.theta * self.theta;
+        let mut sum = 0.0;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.intensity == 0.0 {
+                continue;
+            }
+            if node.children == 0 {
+                sum += node.lights.iter()
+                    .map(|(light_pos, intensity)| intensity / light_pos.distance_squared(pos))
+                    .sum::<f32>();
+                continue;
+            }
+            let dist = node.center.distance_squared(pos);
+            if node.size * node.size < theta_squared * dist {
+                sum += node.intensity / dist;
+            } else {
+                stack.extend(node.children..node.children + 4);
+            }
+        }
+        sum
+    }
+}
+
+// uniform grid of point indices for radius queries
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(points: impl Iterator<Item = Vec2>, cell_size: f32) -> Self {
+        let mut grid = SpatialGrid { cell_size, cells: HashMap::new() };
+        for (i, point) in points.enumerate() {
+            grid.cells.entry(grid.cell(point)).or_default().push(i);
+        }
+        grid
+    }
+
+    fn cell(&self, pos: Vec2) -> (i32, i32) {
+        ((pos.x / self.cell_size).floor() as i32, (pos.y / self.cell_size).floor() as i32)
+    }
+
+    // indices of all points that might be within one cell size of the position
+    pub fn nearby(&self, pos: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = self.cell(pos);
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).map(move |dy| (x + dx, y + dy)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+    }
+}
+
+// compares the accuracy and speed of the quadtree and the uniform grid with the brute force path
+pub fn run_benchmark() {
+    let light_count = 10_000;
+    let sample_count = 10_000;
+    let extent = 100_000.0;
+    let random_pos = || Vec2::new(random_range(-extent, extent), random_range(-extent, extent));
+
+    let lights: Vec<Light> = (0..light_count)
+        .map(|_| Light::new(random_pos(), nannou::color::srgb(1.0, 1.0, 1.0), random_range(0.1, 1.0)))
+        .collect();
+    let samples: Vec<Vec2> = (0..sample_count).map(|_| random_pos()).collect();
+
+    println!("Sensing: {light_count} lights, {sample_count} sample positions");
+    let start = Instant::now();
+    let exact: Vec<f32> = samples.iter().map(|pos| lights[..].field_at(*pos)).collect();
+    let brute_force_time = start.elapsed();
+    println!("  brute force           {:>10.2?}", brute_force_time);
+
+    for theta in [0.0, 0.25, 0.5, 1.0] {
+        let start = Instant::now();
+        let tree = LightTree::new(&lights, theta);
+        let build_time = start.elapsed();
+        let start = Instant::now();
+        let approx: Vec<f32> = samples.iter().map(|pos| tree.field_at(*pos)).collect();
+        let query_time = start.elapsed();
+
+        let errors: Vec<f32> = exact.iter().zip(&approx)
+            .map(|(exact, approx)| ((approx - exact) / exact).abs())
+            .collect();
+        let mean_error = errors.iter().sum::<f32>() / errors.len() as f32;
+        let max_error = errors.iter().copied().fold(0.0, f32::max);
+        println!(
+            "  quadtree theta {theta:<4}   {:>10.2?} (build {:.2?}), mean error {:.2e}, max error {:.2e}, speedup {:.1}x",
+            query_time, build_time, mean_error, max_error,
+            brute_force_time.as_secs_f32() / (build_time + query_time).as_secs_f32(),
+        );
+    }
+
+    let vehicles: Vec<Vec2> = (0..sample_count).map(|_| random_pos()).collect();
+    let collision_distance = 20000.0;
+    println!("Collisions: {light_count} lights, {sample_count} vehicles");
+
+    let start = Instant::now();
+    let brute_force: Vec<Option<usize>> = lights.iter()
+        .map(|light| vehicles.iter().position(|vehicle| light.position.distance_squared(*vehicle) < collision_distance))
+        .collect();
+    let brute_force_time = start.elapsed();
+    println!("  brute force           {:>10.2?}", brute_force_time);
+
+    let start = Instant::now();
+    let grid = SpatialGrid::new(vehicles.iter().copied(), collision_distance.sqrt());
+    let grid_result: Vec<Option<usize>> = lights.iter()
+        .map(|light| grid.nearby(light.position)
+            .filter(|i| light.position.distance_squared(vehicles[*i]) < collision_distance)
+            .min())
+        .collect();
+    let grid_time = start.elapsed();
+    println!(
+        "  uniform grid          {:>10.2?}, identical results: {}, speedup {:.1}x",
+        grid_time,
+        brute_force == grid_result,
+        brute_force_time.as_secs_f32() / grid_time.as_secs_f32(),
+    );
+}
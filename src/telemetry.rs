@@ -0,0 +1,207 @@
+use std::{fs::File, io::{BufWriter, Write}};
+
+use nannou::glam::Vec2;
+use serde::Serialize;
+
+use crate::{braitenberg_vehicle::Vehicle, light::Light};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Jsonl,
+}
+
+impl Format {
+    pub fn to_str(&self) -> &str {
+        match self {
+            Format::Csv => "CSV",
+            Format::Jsonl => "JSONL",
+        }
+    }
+
+    pub fn extension(&self) -> &str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+        }
+    }
+
+    fn from_path(path: &str) -> Self {
+        if path.ends_with(".jsonl") || path.ends_with(".json") {
+            Format::Jsonl
+        } else {
+            Format::Csv
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct VehicleRecord {
+    id: usize,
+    #[serde(rename = "type")]
+    vehicle_type: String,
+    position: Vec2,
+    orientation: f32,
+    velocity: f32,
+    sensors: (f32, f32),
+    motors: (f32, f32),
+}
+
+#[derive(Serialize)]
+struct LightRecord {
+    index: usize,
+    position: Vec2,
+    intensity: f32,
+}
+
+// one line of the jsonl output
+#[derive(Serialize)]
+struct Frame {
+    time: f32,
+    vehicles: Vec<VehicleRecord>,
+    lights: Vec<LightRecord>,
+}
+
+struct Recording {
+    path: String,
+    file: BufWriter<File>,
+    frames: usize,
+}
+
+// writes the state of all vehicles and lights to a file at a fixed interval of simulated time
+pub struct Telemetry {
+    pub format: Format,
+    // seconds of simulated time between two samples
+    pub interval: f32,
+    pub path: String,
+    recording: Option<Recording>,
+    time: f32,
+    next_sample: f32,
+}
+
+impl Telemetry {
+    pub fn new() -> Self {
+        Telemetry {
+            format: Format::Csv,
+            interval: 0.1,
+            path: String::from("telemetry.csv"),
+            recording: None,
+            time: 0.0,
+            next_sample: 0.0,
+        }
+    }
+
+    // --record <path> starts recording right away, --record-interval <seconds> sets the sampling interval
+    pub fn from_args() -> Self {
+        let mut telemetry = Telemetry::new();
+        let args: Vec<String> = std::env::args().collect();
+        let value = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+        if let Some(interval) = value("--record-interval").and_then(|interval| interval.parse().ok()) {
+            telemetry.interval = interval;
+        }
+        if let Some(path) = value("--record") {
+            telemetry.format = Format::from_path(path);
+            telemetry.path = path.clone();
+            if let Err(err) = telemetry.start() {
+                eprintln!("Failed to start recording to {path}: {err}");
+            }
+        }
+        telemetry
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    // number of samples written since recording started
+    pub fn frames(&self) -> usize {
+        self.recording.as_ref().map_or(0, |recording| recording.frames)
+    }
+
+    pub fn start(&mut self) -> std::io::Result<()> {
+        self.stop();
+        let mut file = BufWriter::new(File::create(&self.path)?);
+        if self.format == Format::Csv {
+            writeln!(file, "time,kind,id,type,x,y,orientation,velocity,left_sensor,right_sensor,left_motor,right_motor,intensity")?;
+        }
+        self.recording = Some(Recording { path: self.path.clone(), file, frames: 0 });
+        self.time = 0.0;
+        self.next_sample = 0.0;
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(mut recording) = self.recording.take() {
+            if let Err(err) = recording.file.flush() {
+                eprintln!("Failed to write recording to {}: {err}", recording.path);
+            }
+        }
+    }
+
+    // called once per simulation step, writes a sample whenever the interval has passed
+    pub fn record(&mut self, vehicles: &[Vehicle], lights: &[Light], delta: f32) {
+        if self.recording.is_none() {
+            return;
+        }
+        self.time += delta;
+        if self.time < self.next_sample {
+            return;
+        }
+        self.next_sample = self.time + self.interval;
+        let result = match self.format {
+            Format::Csv => self.write_csv(vehicles, lights),
+            Format::Jsonl => self.write_jsonl(vehicles, lights),
+        };
+        if let Err(err) = result {
+            eprintln!("Failed to record telemetry: {err}");
+            self.stop();
+        } else if let Some(recording) = &mut self.recording {
+            recording.frames += 1;
+        }
+    }
+
+    fn write_csv(&mut self, vehicles: &[Vehicle], lights: &[Light]) -> std::io::Result<()> {
+        let time = self.time;
+        let Some(recording) = &mut self.recording else {
+            return Ok(());
+        };
+        for vehicle in vehicles {
+            writeln!(
+                recording.file,
+                "{time},vehicle,{},{},{},{},{},{},{},{},{},{},",
+                vehicle.id, vehicle.vehicle_type().to_str(), vehicle.position.x, vehicle.position.y,
+                vehicle.orientation, vehicle.velocity,
+                vehicle.sensor_values.0, vehicle.sensor_values.1, vehicle.motor_values.0, vehicle.motor_values.1,
+            )?;
+        }
+        for (index, light) in lights.iter().enumerate() {
+            writeln!(recording.file, "{time},light,{index},,{},{},,,,,,,{}", light.position.x, light.position.y, light.intensity)?;
+        }
+        Ok(())
+    }
+
+    fn write_jsonl(&mut self, vehicles: &[Vehicle], lights: &[Light]) -> std::io::Result<()> {
+        let frame = Frame {
+            time: self.time,
+            vehicles: vehicles.iter()
+                .map(|vehicle| VehicleRecord {
+                    id: vehicle.id,
+                    vehicle_type: vehicle.vehicle_type().to_str().to_string(),
+                    position: vehicle.position,
+                    orientation: vehicle.orientation,
+                    velocity: vehicle.velocity,
+                    sensors: vehicle.sensor_values,
+                    motors: vehicle.motor_values,
+                })
+                .collect(),
+            lights: lights.iter().enumerate()
+                .map(|(index, light)| LightRecord { index, position: light.position, intensity: light.intensity })
+                .collect(),
+        };
+        let Some(recording) = &mut self.recording else {
+            return Ok(());
+        };
+        serde_json::to_writer(&mut recording.file, &frame)?;
+        writeln!(recording.file)
+    }
+}
@@ -0,0 +1,137 @@
+use std::{collections::{HashMap, HashSet, VecDeque}, fs::File, io::Write};
+
+use nannou::{color::{srgb, srgba, Srgb}, glam::Vec2};
+
+use crate::{braitenberg_vehicle::{Vehicle, VehicleType}, camera::Camera};
+
+// minimum distance a vehicle has to travel before a new trail point is recorded
+const SAMPLE_DISTANCE: f32 = 10.0;
+
+pub struct TrailSettings {
+    pub enabled: bool,
+    // number of recorded points kept per vehicle
+    pub length: usize,
+    pub fade: bool,
+    // keeps every point and never fades, traces the full path since the last reset
+    pub persistent: bool,
+    pub weight: f32,
+    pub opacity: f32,
+    // trails take the color of their vehicle type unless one color is chosen for all of them
+    pub color_by_type: bool,
+    pub color: Srgb,
+    pub export_path: String,
+}
+
+impl TrailSettings {
+    fn color(&self, vehicle_type: VehicleType) -> Srgb {
+        if self.color_by_type { vehicle_type.color() } else { self.color }
+    }
+}
+
+struct Trail {
+    vehicle_type: VehicleType,
+    points: VecDeque<Vec2>,
+}
+
+pub struct Trails {
+    pub settings: TrailSettings,
+    trails: HashMap<usize, Trail>,
+}
+
+impl Trails {
+    pub fn new() -> Self {
+        Trails {
+            settings: TrailSettings {
+                enabled: false,
+                length: 300,
+                fade: true,
+                persistent: false,
+                weight: 3.0,
+                opacity: 0.6,
+                color_by_type: true,
+                color: srgb(1.0, 1.0, 1.0),
+                export_path: String::from("trails.svg"),
+            },
+            trails: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.trails.clear();
+    }
+
+    // adds the current vehicle positions to the trails
+    pub fn record(&mut self, vehicles: &[Vehicle]) {
+        if !self.settings.enabled {
+            return;
+        }
+        for vehicle in vehicles {
+            let trail = self.trails.entry(vehicle.id).or_insert_with(|| Trail {
+                vehicle_type: vehicle.vehicle_type(),
+                points: VecDeque::new(),
+            });
+            if trail.points.back().is_none_or(|last| last.distance(vehicle.position) >= SAMPLE_DISTANCE) {
+                trail.points.push_back(vehicle.position);
+            }
+            while !self.settings.persistent && trail.points.len() > self.settings.length {
+                trail.points.pop_front();
+            }
+        }
+        // trails of removed vehicles are only kept as part of a persistent trace
+        if !self.settings.persistent {
+            let alive: HashSet<usize> = vehicles.iter().map(|vehicle| vehicle.id).collect();
+            self.trails.retain(|id, _| alive.contains(id));
+        }
+    }
+
+    // returns the number of primitives
+    pub fn draw(&self, draw: &nannou::draw::Draw, camera: &Camera) -> usize {
+        if !self.settings.enabled {
+            return 0;
+        }
+        let mut lines = 0;
+        for trail in self.trails.values() {
+            if trail.points.len() < 2 {
+                continue;
+            }
+            let color = self.settings.color(trail.vehicle_type);
+            let fade = self.settings.fade && !self.settings.persistent;
+            let count = trail.points.len() as f32;
+            let points = trail.points.iter().enumerate().map(|(i, point)| {
+                let alpha = (if fade { i as f32 / count } else { 1.0 }) * self.settings.opacity;
+                ((*point - camera.position) * camera.zoom, srgba(color.red, color.green, color.blue, alpha))
+            });
+            draw.polyline()
+                .weight(self.settings.weight * camera.zoom)
+                .points_colored(points);
+            lines += 1;
+        }
+        lines
+    }
+
+    // writes all trails as svg polylines in world coordinates, the y axis is flipped to point up like in the simulation
+    pub fn export_svg(&self) -> std::io::Result<()> {
+        let points = self.trails.values().flat_map(|trail| trail.points.iter());
+        let min = points.clone().fold(Vec2::splat(f32::MAX), |min, point| min.min(*point));
+        let max = points.fold(Vec2::splat(f32::MIN), |max, point| max.max(*point));
+        let (min, max) = if min.x > max.x { (Vec2::ZERO, Vec2::ONE) } else { (min, max) };
+        let size = (max - min).max(Vec2::ONE);
+
+        let mut file = File::create(&self.settings.export_path)?;
+        writeln!(file, r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#, min.x, -max.y, size.x, size.y)?;
+        writeln!(file, r#"<g transform="scale(1,-1)" fill="none" stroke-width="{}" stroke-opacity="{}">"#, self.settings.weight, self.settings.opacity)?;
+        for trail in self.trails.values() {
+            let color = self.settings.color(trail.vehicle_type);
+            let points: Vec<String> = trail.points.iter().map(|point| format!("{},{}", point.x, point.y)).collect();
+            writeln!(
+                file,
+                r#"<polyline stroke="rgb({},{},{})" points="{}"/>"#,
+                (color.red * 255.0) as u8, (color.green * 255.0) as u8, (color.blue * 255.0) as u8,
+                points.join(" "),
+            )?;
+        }
+        writeln!(file, "</g>")?;
+        writeln!(file, "</svg>")?;
+        Ok(())
+    }
+}
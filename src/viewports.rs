@@ -0,0 +1,82 @@
+use nannou::{geom::Rect, glam::Vec2};
+
+use crate::camera::{Camera, CameraMode};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Single,
+    SideBySide,
+    Stacked,
+    Grid,
+}
+
+impl Layout {
+    pub const ALL: [Layout; 4] = [Layout::Single, Layout::SideBySide, Layout::Stacked, Layout::Grid];
+
+    pub fn to_str(&self) -> &str {
+        match self {
+            Layout::Single => "Single",
+            Layout::SideBySide => "Side by Side",
+            Layout::Stacked => "Stacked",
+            Layout::Grid => "2 x 2 Grid",
+        }
+    }
+
+    // screen area of every viewport, the first one belongs to the main camera
+    pub fn rects(&self, window: Rect) -> Vec<Rect> {
+        let (columns, rows) = match self {
+            Layout::Single => (1, 1),
+            Layout::SideBySide => (2, 1),
+            Layout::Stacked => (1, 2),
+            Layout::Grid => (2, 2),
+        };
+        let size = window.wh() / Vec2::new(columns as f32, rows as f32);
+        (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                // rows are counted from the top of the window
+                let center = Vec2::new(window.left(), window.top())
+                    + Vec2::new(column as f32 + 0.5, -(row as f32 + 0.5)) * size;
+                Rect::from_xy_wh(center, size)
+            })
+            .collect()
+    }
+}
+
+// a second view on the same world with its own camera and follow target
+pub struct View {
+    pub camera: Camera,
+    pub camera_mode: CameraMode,
+    pub follow_vehicle_indx: usize,
+}
+
+// the viewports besides the main one, which uses the camera of the model
+pub struct Viewports {
+    pub layout: Layout,
+    pub views: Vec<View>,
+    // viewport under the mouse, it receives the camera controls
+    pub active: usize,
+}
+
+impl Viewports {
+    pub fn new() -> Self {
+        Viewports {
+            layout: Layout::Single,
+            views: Vec::new(),
+            active: 0,
+        }
+    }
+
+    // adds or removes views to match the layout, new views start at the main camera
+    pub fn resize(&mut self, count: usize, main_camera: &Camera) {
+        self.views.truncate(count.saturating_sub(1));
+        while self.views.len() + 1 < count {
+            self.views.push(View {
+                camera: main_camera.clone(),
+                camera_mode: CameraMode::Free,
+                follow_vehicle_indx: self.views.len() + 1,
+            });
+        }
+        self.active = self.active.min(count.saturating_sub(1));
+    }
+}